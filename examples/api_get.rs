@@ -1,25 +1,16 @@
-use api_builder::{api_rest_client, error::APIError, APIErrorKind, ReqwestClient, RestClient};
-use luarmor::{models::LuarmorMessage, LuarmorClient};
+use luarmor::LuarmorClient;
 
-// Create our own client for sending requests
-#[derive(Default, ReqwestClient)]
-struct Client {
-    client: reqwest::blocking::Client,
-}
-#[api_rest_client(error = LuarmorMessage, base = "\"https://api.luarmor.net\"")]
-impl RestClient for Client { }
-
-fn main() -> Result<(), APIError<LuarmorMessage>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialisation
-    dotenv::dotenv().map_err(APIErrorKind::from_any_error)?;
-    let api_key = std::env::var("API_KEY").map_err(APIErrorKind::from_any_error)?;
+    dotenv::dotenv()?;
+    let api_key = std::env::var("API_KEY")?;
 
-    // Construct the client
-    let client = LuarmorClient::new(api_key, Client::default());
+    // Construct the client, using the built-in blocking backend
+    let client = LuarmorClient::from_api_key_blocking(api_key);
 
     // Log each request
     println!("API status:\n{:?}\n---", client.status()?);
     println!("API key details:\n{:?}\n---", client.details()?);
-    println!("API Key stats:\n{:?}\n---", client.stats(false)?);
+    println!("API Key stats:\n{:?}\n---", client.stats()?);
     Ok(())
-}
\ No newline at end of file
+}
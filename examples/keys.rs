@@ -1,9 +1,9 @@
-use api_builder::{api_rest_client, error::APIError, APIErrorKind, ReqwestClient, RestClient};
+use api_builder::{api_rest_client, ReqwestClient, RestClient};
 use luarmor::{
-    LuarmorClient,
+    LuarmorClient, LuarmorError,
     models::{
-        LuarmorMessage,
-        v3::projects::users::{CreateUser, GetUsers, LinkDiscordId, ResetHwid, UpdateUser},
+        DiscordId, Hwid, ProjectId,
+        v3::projects::users::{KeyOwner, LifetimeKey, NewUser, UserChanges},
     },
 };
 use reqwest::{blocking::ClientBuilder, Proxy};
@@ -13,14 +13,14 @@ use reqwest::{blocking::ClientBuilder, Proxy};
 struct Client {
     client: reqwest::blocking::Client,
 }
-#[api_rest_client(error = LuarmorMessage, base = "\"https://api.luarmor.net\"")]
+#[api_rest_client(error = LuarmorError, base = "\"https://api.luarmor.net\"")]
 impl RestClient for Client {}
 
-fn main() -> Result<(), APIError<LuarmorMessage>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialisation
-    dotenv::dotenv().map_err(APIErrorKind::from_any_error)?;
-    let api_key = std::env::var("API_KEY").map_err(APIErrorKind::from_any_error)?;
-    let project_id = std::env::var("PROJECT_ID").map_err(APIErrorKind::from_any_error)?;
+    dotenv::dotenv()?;
+    let api_key = std::env::var("API_KEY")?;
+    let project_id: ProjectId = std::env::var("PROJECT_ID")?.parse()?;
 
     // Construct the client, this one is proxy to Fiddler for testing
     println!("{api_key} - {project_id}");
@@ -31,75 +31,48 @@ fn main() -> Result<(), APIError<LuarmorMessage>> {
     });
 
     // Creating a key...
-    let user_key = client.create_user(
-        CreateUser::builder()
-            .project_id(project_id.as_str())
-            .identifier("test")
-            .build(),
-    )?;
+    let project = client.project(&project_id);
+    let user_key = project.create_user(NewUser {
+        key: LifetimeKey {
+            owner: Some(KeyOwner::Identifier("test".parse::<Hwid>()?.into())),
+        }
+        .into(),
+        ..Default::default()
+    })?;
     println!("Created key - {}", user_key);
 
     // See if we can find it
-    let users = client.users(
-        GetUsers::builder()
-            .project_id(project_id.as_str())
-            .user_key(user_key.as_str())
-            .build(),
-    )?;
-    assert_eq!(users.len(), 1);
+    let user = project.user(&user_key);
+    assert!(user.get()?.is_some());
     println!("Found key!");
 
     // Set the discord
-    let discord_id = "398271060514045964";
-    client.link_discord(
-        LinkDiscordId::builder()
-            .project_id(project_id.as_str())
-            .user_key(user_key.as_str())
-            .discord_id(discord_id)
-            .build(),
-    )?;
+    let discord_id: DiscordId = "398271060514045964".parse()?;
+    user.link_discord(&discord_id, false)?;
     println!("Linked discord");
 
     // Reset HWID
-    client.reset_hwid(
-        ResetHwid::builder()
-            .project_id(project_id.as_str())
-            .user_key(user_key.as_str())
-            .force(true)
-            .build(),
-    )?;
+    user.reset_hwid(true)?;
     println!("Reset hwid");
 
     // Update the key
     let note = "sigma";
-    client.update_user(
-        UpdateUser::builder()
-            .project_id(project_id.as_str())
-            .user_key(user_key.as_str())
-            .note(note)
-            .build(),
-    )?;
+    user.update(UserChanges {
+        note: note.into(),
+        ..Default::default()
+    })?;
     println!("Set note");
 
-    // Grab the key again
-    let users = client.users(
-        GetUsers::builder()
-            .project_id(project_id.as_str())
-            .user_key(user_key.as_str())
-            .build(),
-    )?;
-    assert_eq!(users.len(), 1);
-
-    // Check if our changes were successful
-    let user = users.first().unwrap();
-    assert_eq!(user.note, Some(note.to_string()));
-    assert_eq!(user.identifier, None);
-    assert_eq!(user.discord_id, Some(discord_id.to_string()));
+    // Grab the key again, and check if our changes were successful
+    let found = user.get()?.unwrap();
+    assert_eq!(found.note, Some(note.to_string()));
+    assert_eq!(found.identifier, None);
+    assert_eq!(found.discord_id, Some(discord_id));
 
     println!("Found key and changes found!");
 
     // Finally delete the key
-    client.delete_user(&project_id, &user_key)?;
+    user.delete()?;
     println!("Deleted key - {}", user_key);
 
     Ok(())
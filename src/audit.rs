@@ -0,0 +1,487 @@
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use api_builder::{AsyncClient, Bytes, Client, RestClient, Url, error::APIError};
+use http::{Request, Response};
+use serde::{Serialize, de::IgnoredAny};
+use serde_json::{Map, Value};
+use time::OffsetDateTime;
+
+use crate::{
+    models::{LuarmorMessage, LuarmorResponse, ProjectId, UserKey},
+    route::Route,
+    secret::REDACTED,
+};
+
+/// Fields which are replaced with `[REDACTED]` before a body is recorded.
+const SECRET_FIELDS: &[&str] = &["unban_token"];
+/// Fields which are replaced with their length, e.g. `[1024 bytes]`, so scripts aren't leaked to the log.
+const LENGTH_FIELDS: &[&str] = &["script"];
+
+/// A mutating request sent through [Audit], and how it went.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct AuditRecord {
+    /// When the response, or error, was received.
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// Who made the request, see [Audit::with_actor].
+    pub actor: Option<String>,
+    /// The endpoint, e.g. `delete_user`, see [Route::as_str].
+    pub endpoint: &'static str,
+    pub method: String,
+    /// The path, without the query string.
+    pub path: String,
+    pub project_id: Option<ProjectId>,
+    /// The key the request targets, or the key it created.
+    ///
+    /// Always `None` for [Route::UnblacklistUser], which only carries the unban token.
+    pub user_key: Option<UserKey>,
+    /// The JSON body, or the query parameters if there is no body, with secrets redacted.
+    pub body: Option<Value>,
+    /// The status code, if a response was received.
+    pub status: Option<u16>,
+    /// If Luarmor reported success.
+    pub success: bool,
+    /// The message Luarmor responded with, if any.
+    pub message: Option<LuarmorMessage>,
+    /// Why no response was received, if so.
+    pub error: Option<String>,
+}
+impl AuditRecord {
+    fn new<B: AsRef<[u8]>>(request: &Request<B>, route: Route, actor: Option<&str>) -> Self {
+        let uri = request.uri();
+        let segments: Vec<&str> = uri.path().split('/').collect();
+        let project_id = segments
+            .iter()
+            .position(|x| *x == "projects")
+            .and_then(|i| segments.get(i + 1))
+            .and_then(|x| x.parse().ok());
+
+        let mut body = serde_json::from_slice::<Map<String, Value>>(request.body().as_ref())
+            .ok()
+            .or_else(|| {
+                let pairs = uri.query()?.split('&').filter_map(|x| x.split_once('='));
+                Some(
+                    pairs
+                        .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+                        .collect(),
+                )
+            });
+        let user_key = body
+            .as_ref()
+            .and_then(|x| x.get("user_key"))
+            .and_then(|x| x.as_str())
+            .and_then(|x| x.parse().ok());
+        if let Some(body) = &mut body {
+            for field in SECRET_FIELDS {
+                if let Some(value) = body.get_mut(*field) {
+                    *value = REDACTED.into();
+                }
+            }
+            for field in LENGTH_FIELDS {
+                if let Some(value) = body.get_mut(*field) {
+                    let len = value.as_str().map_or(0, str::len);
+                    *value = format!("[{len} bytes]").into();
+                }
+            }
+        }
+
+        Self {
+            timestamp: OffsetDateTime::now_utc(),
+            actor: actor.map(str::to_string),
+            endpoint: route.as_str(),
+            method: request.method().to_string(),
+            path: uri.path().to_string(),
+            project_id,
+            user_key,
+            body: body.map(Value::Object),
+            status: None,
+            success: false,
+            message: None,
+            error: None,
+        }
+    }
+
+    fn outcome<E: fmt::Display>(mut self, result: &Result<Response<Bytes>, APIError<E>>) -> Self {
+        self.timestamp = OffsetDateTime::now_utc();
+        match result {
+            Ok(response) => {
+                self.status = Some(response.status().as_u16());
+                if let Ok(x) =
+                    serde_json::from_slice::<LuarmorResponse<IgnoredAny>>(response.body())
+                {
+                    self.success = x.success;
+                    self.message = Some(x.message);
+                }
+
+                // Record which key was created
+                if self.user_key.is_none() {
+                    self.user_key = serde_json::from_slice::<Map<String, Value>>(response.body())
+                        .ok()
+                        .and_then(|x| x.get("user_key")?.as_str()?.parse().ok());
+                }
+            }
+            Err(err) => self.error = Some(redact_query(&err.to_string())),
+        }
+        self
+    }
+}
+
+/// Replaces the value of any [SECRET_FIELDS] query parameter within `text`.
+///
+/// HTTP client errors, e.g. `reqwest::Error`, include the URL, which holds the token for
+/// [crate::models::v3::projects::users::UnblacklistUser].
+fn redact_query(text: &str) -> String {
+    let mut redacted = text.to_string();
+    for field in SECRET_FIELDS {
+        let key = format!("{field}=");
+        let mut from = 0;
+        while let Some(start) = redacted[from..].find(&key).map(|x| from + x + key.len()) {
+            let end = redacted[start..]
+                .find(|c: char| c == '&' || c == '#' || c == ')' || c == '"' || c.is_whitespace())
+                .map_or(redacted.len(), |x| start + x);
+            redacted.replace_range(start..end, REDACTED);
+            from = start + REDACTED.len();
+        }
+    }
+    redacted
+}
+
+/// Where [Audit] sends its records.
+///
+/// Recording can't fail the request, as it has already been sent, so sinks must keep track of their own errors.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: AuditRecord);
+}
+impl<S: AuditSink + ?Sized> AuditSink for Arc<S> {
+    fn record(&self, record: AuditRecord) {
+        (**self).record(record)
+    }
+}
+/// Keeps the records in memory, e.g. for tests.
+impl AuditSink for Mutex<Vec<AuditRecord>> {
+    fn record(&self, record: AuditRecord) {
+        self.lock().unwrap().push(record)
+    }
+}
+
+/// Appends each record to `W` as a line of JSON, flushing after each one.
+#[derive(Debug)]
+pub struct JsonLinesSink<W = File> {
+    writer: Mutex<W>,
+    error: Mutex<Option<io::Error>>,
+}
+impl JsonLinesSink {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+}
+impl<W: Write> JsonLinesSink<W> {
+    /// Creates an instance.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Takes the first error since the last call, any records which failed to write are lost.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.error.lock().unwrap().take()
+    }
+
+    fn write(&self, record: &AuditRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&line)?;
+        writer.flush()
+    }
+}
+impl<W: Write + Send> AuditSink for JsonLinesSink<W> {
+    fn record(&self, record: AuditRecord) {
+        if let Err(err) = self.write(&record) {
+            self.error.lock().unwrap().get_or_insert(err);
+        }
+    }
+}
+
+/// Wraps a client, recording every mutating request (see [Route::is_mutating]) to a sink.
+///
+/// Reads pass through untouched.
+#[derive(Debug)]
+pub struct Audit<C, S> {
+    client: C,
+    sink: Arc<S>,
+    actor: Option<Arc<str>>,
+}
+impl<C: Clone, S> Clone for Audit<C, S> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            sink: self.sink.clone(),
+            actor: self.actor.clone(),
+        }
+    }
+}
+impl<C, S> Audit<C, S> {
+    /// Creates an instance.
+    pub fn new(client: C, sink: S) -> Self {
+        Self {
+            client,
+            sink: Arc::new(sink),
+            actor: None,
+        }
+    }
+
+    /// Sets who requests are recorded as being made by.
+    ///
+    /// Clone the client to record a different actor while sharing the sink.
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into().into());
+        self
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// The sink records are sent to.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// The record to finish once the request is sent, if it should be audited.
+    fn start<B: AsRef<[u8]>>(&self, request: &Request<B>) -> Option<AuditRecord> {
+        let route = Route::from_request(request).filter(|x| x.is_mutating())?;
+        Some(AuditRecord::new(request, route, self.actor.as_deref()))
+    }
+}
+impl<C, S> RestClient for Audit<C, S>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        self.client.rest_endpoint(path)
+    }
+}
+impl<C, S> Client for Audit<C, S>
+where
+    C: Client,
+    C::Error: fmt::Display,
+    S: AuditSink,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let record = self.start(&request);
+        let result = self.client.rest(request);
+        if let Some(record) = record {
+            self.sink.record(record.outcome(&result));
+        }
+        result
+    }
+}
+impl<C, S> AsyncClient for Audit<C, S>
+where
+    C: AsyncClient + Sync,
+    C::Error: fmt::Display + Send,
+    S: AuditSink,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let record = self.start(&request);
+        let result = self.client.rest_async(request).await;
+        if let Some(record) = record {
+            self.sink.record(record.outcome(&result));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        models::{
+            UnbanToken,
+            v3::projects::users::{NewUser, UserFilter},
+        },
+        testing::{FakeLuarmor, FakeProject},
+    };
+
+    fn record(method: &str, uri: &str, body: Value) -> AuditRecord {
+        let body = match body {
+            Value::Null => Vec::new(),
+            x => x.to_string().into_bytes(),
+        };
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(body)
+            .unwrap();
+        let route = Route::from_request(&request).unwrap();
+        AuditRecord::new(&request, route, None)
+    }
+
+    #[test]
+    fn scripts_are_recorded_by_length() {
+        let record = record(
+            "PUT",
+            "https://api.luarmor.net/v3/projects/abc/scripts/def",
+            json!({ "script": "print('secret')", "silent": true }),
+        );
+        assert_eq!(
+            record.body,
+            Some(json!({ "script": "[15 bytes]", "silent": true }))
+        );
+    }
+
+    #[test]
+    fn unban_tokens_are_redacted() {
+        let token = "a".repeat(32);
+        let record = record(
+            "GET",
+            &format!("https://api.luarmor.net/v3/projects/abc/users/unban?unban_token={token}"),
+            Value::Null,
+        );
+        assert_eq!(record.body, Some(json!({ "unban_token": REDACTED })));
+        assert_eq!(record.user_key, None);
+    }
+
+    #[test]
+    fn redacts_query_secrets() {
+        assert_eq!(
+            redact_query("error for url (http://x/unban?unban_token=abc&b=1)"),
+            "error for url (http://x/unban?unban_token=[REDACTED]&b=1)"
+        );
+        assert_eq!(
+            redact_query("unban_token=a unban_token=b"),
+            "unban_token=[REDACTED] unban_token=[REDACTED]"
+        );
+        assert_eq!(redact_query("http://x/users"), "http://x/users");
+    }
+
+    /// Fails every request with a transport error, which includes the URL.
+    #[derive(Clone)]
+    struct Unreachable;
+    impl RestClient for Unreachable {
+        type Error = crate::LuarmorError;
+
+        fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+            // Nothing listens on the discard port, so this fails without leaving the machine
+            Ok(Url::parse("http://127.0.0.1:9")?.join(path)?)
+        }
+    }
+    impl Client for Unreachable {
+        fn rest(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            let err = reqwest::blocking::get(request.uri().to_string()).unwrap_err();
+            Err(err.into())
+        }
+    }
+
+    #[test]
+    fn failed_unbans_hide_the_token() {
+        let token: UnbanToken = "a".repeat(32).parse().unwrap();
+        let audit = Audit::new(Unreachable, Mutex::new(Vec::new()));
+        let client = crate::LuarmorClient::new("secret", audit.clone());
+        client
+            .unblacklist(&"project".parse().unwrap(), &token)
+            .unwrap_err();
+
+        let records = audit.sink().lock().unwrap();
+        let error = records[0].error.as_deref().unwrap();
+        assert!(!error.contains(token.as_str()), "{error}");
+        assert!(error.contains(REDACTED), "{error}");
+    }
+
+    #[test]
+    fn reads_skip_the_sink() {
+        let fake = FakeLuarmor::new("secret").with_project(FakeProject::new("project", "Project"));
+        let audit = Audit::new(fake, Mutex::new(Vec::new()));
+        let client = crate::LuarmorClient::new("secret", audit.clone());
+        let project = "project".parse().unwrap();
+
+        client.status().unwrap();
+        client
+            .project(&project)
+            .users(UserFilter::default())
+            .unwrap();
+        assert!(audit.sink().lock().unwrap().is_empty());
+
+        let user_key = client
+            .project(&project)
+            .create_user(NewUser::default())
+            .unwrap();
+        let records = audit.sink().lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].endpoint, "create_user");
+        assert_eq!(records[0].user_key, Some(user_key));
+        assert!(records[0].success);
+    }
+
+    #[test]
+    fn json_lines_are_framed() {
+        let sink = JsonLinesSink::new(Vec::new());
+        sink.record(record(
+            "DELETE",
+            "https://api.luarmor.net/v3/projects/abc/users?user_key=key1",
+            Value::Null,
+        ));
+        sink.record(record(
+            "PATCH",
+            "https://api.luarmor.net/v3/projects/abc/users",
+            json!({ "user_key": "key2", "note": "line\nbreak" }),
+        ));
+        assert!(sink.take_error().is_none());
+
+        let written = String::from_utf8(sink.writer.lock().unwrap().clone()).unwrap();
+        assert!(written.ends_with('\n'));
+        let lines: Vec<Value> = written
+            .lines()
+            .map(|x| serde_json::from_str(x).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["user_key"], "key1");
+        assert_eq!(lines[1]["body"]["note"], "line\nbreak");
+    }
+
+    /// Fails every write.
+    struct Broken;
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sinks_keep_the_first_error() {
+        let sink = JsonLinesSink::new(Broken);
+        let uri = "https://api.luarmor.net/v3/projects/abc/users?user_key=key1";
+        sink.record(record("DELETE", uri, Value::Null));
+        sink.record(record("DELETE", uri, Value::Null));
+
+        assert_eq!(sink.take_error().unwrap().to_string(), "disk full");
+        assert!(sink.take_error().is_none());
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(feature = "reqwest-blocking")]
+use api_builder::ReqwestClient;
+use api_builder::{ReqwestAsyncClient, RestClient, Url, api_rest_client, error::APIError};
+
+use crate::{ApiKey, LuarmorClient, LuarmorError};
+
+/// The base URL of the official Luarmor API.
+pub const LUARMOR_API_BASE: &str = "https://api.luarmor.net";
+
+/// A ready-made async backend, powered by [reqwest::Client].
+#[derive(Clone, Debug, ReqwestAsyncClient)]
+pub struct ReqwestBackend {
+    async_client: reqwest::Client,
+    base: Url,
+}
+impl ReqwestBackend {
+    /// Creates an instance which targets the official API.
+    pub fn new(async_client: reqwest::Client) -> Self {
+        Self::with_base_url(
+            async_client,
+            Url::parse(LUARMOR_API_BASE).expect("valid base url"),
+        )
+    }
+
+    /// Creates an instance which targets `base`, e.g. a local mock server.
+    pub fn with_base_url(async_client: reqwest::Client, base: Url) -> Self {
+        Self { async_client, base }
+    }
+}
+impl Default for ReqwestBackend {
+    fn default() -> Self {
+        Self::new(reqwest::Client::default())
+    }
+}
+#[api_rest_client(error = LuarmorError)]
+impl RestClient for ReqwestBackend {
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        Ok(self.base.join(path)?)
+    }
+}
+
+/// A ready-made blocking backend, powered by [reqwest::blocking::Client].
+#[cfg(feature = "reqwest-blocking")]
+#[derive(Clone, Debug, ReqwestClient)]
+pub struct BlockingReqwestBackend {
+    client: reqwest::blocking::Client,
+    base: Url,
+}
+#[cfg(feature = "reqwest-blocking")]
+impl BlockingReqwestBackend {
+    /// Creates an instance which targets the official API.
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        Self::with_base_url(
+            client,
+            Url::parse(LUARMOR_API_BASE).expect("valid base url"),
+        )
+    }
+
+    /// Creates an instance which targets `base`, e.g. a local mock server.
+    pub fn with_base_url(client: reqwest::blocking::Client, base: Url) -> Self {
+        Self { client, base }
+    }
+}
+#[cfg(feature = "reqwest-blocking")]
+impl Default for BlockingReqwestBackend {
+    fn default() -> Self {
+        Self::new(reqwest::blocking::Client::default())
+    }
+}
+#[cfg(feature = "reqwest-blocking")]
+#[api_rest_client(error = LuarmorError)]
+impl RestClient for BlockingReqwestBackend {
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        Ok(self.base.join(path)?)
+    }
+}
+
+impl LuarmorClient<ReqwestBackend> {
+    /// Creates an instance using the default async [ReqwestBackend].
+    pub fn from_api_key(api_key: impl Into<ApiKey>) -> Self {
+        Self::new(api_key, ReqwestBackend::default())
+    }
+}
+#[cfg(feature = "reqwest-blocking")]
+impl LuarmorClient<BlockingReqwestBackend> {
+    /// Creates an instance using the default [BlockingReqwestBackend].
+    pub fn from_api_key_blocking(api_key: impl Into<ApiKey>) -> Self {
+        Self::new(api_key, BlockingReqwestBackend::default())
+    }
+}
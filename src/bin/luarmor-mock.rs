@@ -0,0 +1,218 @@
+// A local stand-in for the Luarmor API, built on `luarmor::testing::FakeLuarmor`.
+//
+// Usage: luarmor-mock [--port 8787] [--api-key KEY] [--seed seed.json]
+
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    process, thread,
+};
+
+use http::{
+    HeaderName, HeaderValue, Request,
+    header::{CONTENT_LENGTH, TRANSFER_ENCODING},
+};
+use luarmor::testing::{FakeLuarmor, FakeSeed, FakeState};
+
+struct Args {
+    port: u16,
+    api_key: String,
+    state: FakeState,
+}
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Self {
+            port: 8787,
+            api_key: "test".to_string(),
+            state: FakeState::default(),
+        };
+
+        let mut iter = env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            let mut value = || iter.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--port" => args.port = value()?.parse().map_err(|err| format!("{err}"))?,
+                "--api-key" => args.api_key = value()?,
+                "--seed" => {
+                    let path = value()?;
+                    let data = fs::read(&path).map_err(|err| format!("{path}: {err}"))?;
+                    let seed: FakeSeed =
+                        serde_json::from_slice(&data).map_err(|err| format!("{path}: {err}"))?;
+                    args.state = seed.try_into().map_err(|err| format!("{path}: {err}"))?;
+                }
+                _ => return Err(format!("unknown argument {arg}")),
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Reads a single HTTP/1.1 request from `stream`.
+fn read_request(stream: impl Read) -> io::Result<Request<Vec<u8>>> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(uri)) = (parts.next(), parts.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+    let mut request = Request::builder().method(method).uri(uri);
+
+    let mut content_length = 0;
+    let mut chunked = false;
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        let (name, value) = line.split_once(':').ok_or(invalid("malformed header"))?;
+        let name = HeaderName::try_from(name.trim()).map_err(|_| invalid("invalid header"))?;
+        let value = HeaderValue::try_from(value.trim()).map_err(|_| invalid("invalid header"))?;
+        if name == CONTENT_LENGTH {
+            content_length = value
+                .to_str()
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .ok_or(invalid("invalid content-length"))?;
+        } else if name == TRANSFER_ENCODING {
+            // Chunked must be the final encoding, and no others are supported
+            chunked = value.as_bytes().eq_ignore_ascii_case(b"chunked");
+            if !chunked {
+                return Err(invalid("unsupported transfer-encoding"));
+            }
+        }
+        request = request.header(name, value);
+    }
+
+    let body = if chunked {
+        read_chunked(&mut reader)?
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        body
+    };
+    request.body(body).map_err(|err| invalid(&err.to_string()))
+}
+
+/// Reads a `Transfer-Encoding: chunked` body, discarding any extensions and trailers.
+fn read_chunked(reader: &mut impl BufRead) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| invalid("invalid chunk size"))?;
+        if size == 0 {
+            break;
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        line.clear();
+        reader.read_line(&mut line)?;
+        if !line.trim_end().is_empty() {
+            return Err(invalid("malformed chunk"));
+        }
+    }
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            return Ok(body);
+        }
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn serve(fake: &FakeLuarmor, mut stream: TcpStream) -> io::Result<()> {
+    let request = read_request(&stream)?;
+    let response = fake.handle(&request);
+    println!(
+        "{} {} -> {}",
+        request.method(),
+        request.uri(),
+        response.status()
+    );
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n",
+        response.status().as_u16(),
+        response.status().canonical_reason().unwrap_or_default()
+    )?;
+    for (name, value) in response.headers() {
+        write!(stream, "{name}: ")?;
+        stream.write_all(value.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+    }
+    write!(
+        stream,
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        response.body().len()
+    )?;
+    stream.write_all(response.body())?;
+    stream.flush()
+}
+
+fn main() {
+    let args = Args::parse().unwrap_or_else(|err| {
+        eprintln!("{err}\nUsage: luarmor-mock [--port 8787] [--api-key KEY] [--seed seed.json]");
+        process::exit(2);
+    });
+
+    let fake = FakeLuarmor::with_state(args.api_key, args.state);
+    let listener = TcpListener::bind(("127.0.0.1", args.port)).unwrap_or_else(|err| {
+        eprintln!("failed to bind to port {}: {err}", args.port);
+        process::exit(1);
+    });
+    println!("Listening on http://{}", listener.local_addr().unwrap());
+
+    for stream in listener.incoming().flatten() {
+        let fake = fake.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve(&fake, stream) {
+                eprintln!("failed to serve request: {err}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_content_length_body() {
+        let request =
+            read_request(&b"POST /v3/projects/p/users HTTP/1.1\r\ncontent-length: 2\r\n\r\n{}"[..])
+                .unwrap();
+        assert_eq!(request.body(), b"{}");
+    }
+
+    #[test]
+    fn reads_chunked_body() {
+        let request = read_request(
+            &b"PATCH /v3/projects/p/users HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                5;ext=1\r\n{\"a\":\r\n3\r\n12}\r\n0\r\nx-trailer: 1\r\n\r\n"[..],
+        )
+        .unwrap();
+        assert_eq!(request.body(), br#"{"a":12}"#);
+    }
+
+    #[test]
+    fn rejects_unsupported_encodings() {
+        let err = read_request(&b"POST / HTTP/1.1\r\ntransfer-encoding: gzip, chunked\r\n\r\n"[..])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,208 @@
+use std::{sync::Mutex, thread};
+
+use crate::{LuarmorError, models::UserKey};
+
+/// A key created by a bulk operation.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct BulkCreated {
+    /// The position of the spec which created the key.
+    pub index: usize,
+    pub user_key: UserKey,
+}
+
+/// A spec which failed within a bulk operation.
+#[derive(Clone, Debug)]
+pub struct BulkFailure {
+    /// The position of the spec which failed.
+    pub index: usize,
+    pub error: LuarmorError,
+}
+
+/// The outcome of [crate::LuarmorClient::create_users_bulk], ordered by spec.
+#[derive(Clone, Debug, Default)]
+pub struct BulkReport {
+    pub created: Vec<BulkCreated>,
+    pub failed: Vec<BulkFailure>,
+}
+impl BulkReport {
+    pub(crate) fn new(mut results: Vec<(usize, Result<UserKey, LuarmorError>)>) -> Self {
+        results.sort_by_key(|(index, _)| *index);
+
+        let mut report = Self::default();
+        for (index, result) in results {
+            match result {
+                Ok(user_key) => report.created.push(BulkCreated { index, user_key }),
+                Err(error) => report.failed.push(BulkFailure { index, error }),
+            }
+        }
+        report
+    }
+
+    /// If every spec succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Runs `f` over `items` on up to `concurrency` threads, returning each result with the item's position.
+pub(crate) fn fan_out<I, T, F>(items: I, concurrency: usize, f: F) -> Vec<(usize, T)>
+where
+    I: IntoIterator,
+    I::IntoIter: Send,
+    T: Send,
+    F: Fn(I::Item) -> T + Sync,
+{
+    let items = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1) {
+            scope.spawn(|| {
+                loop {
+                    // Don't hold the lock while working on the item
+                    let next = items.lock().unwrap().next();
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+
+                    let result = f(item);
+                    results.lock().unwrap().push((index, result));
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use api_builder::{AsyncClient, Bytes, Client, RestClient, Url, error::APIError};
+    use http::{Request, Response, StatusCode};
+
+    use super::*;
+    use crate::{
+        LuarmorClient,
+        models::v3::projects::users::NewUser,
+        testing::{FakeLuarmor, FakeProject},
+    };
+
+    /// Forwards requests to a [FakeLuarmor], counting how many are in flight.
+    ///
+    /// Requests with the note `fail` are answered with `500`.
+    #[derive(Clone)]
+    struct Tracked {
+        fake: FakeLuarmor,
+        in_flight: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+    impl Tracked {
+        fn new() -> Self {
+            Self {
+                fake: FakeLuarmor::new("secret")
+                    .with_project(FakeProject::new("project", "Project")),
+                in_flight: Arc::default(),
+                peak: Arc::default(),
+            }
+        }
+
+        fn enter(&self) {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(in_flight, Ordering::SeqCst);
+        }
+
+        fn leave(&self, request: &Request<Vec<u8>>) -> Response<Bytes> {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if String::from_utf8_lossy(request.body()).contains("\"fail\"") {
+                let mut response = Response::new(Bytes::new());
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                return response;
+            }
+            self.fake.handle(request)
+        }
+    }
+    impl RestClient for Tracked {
+        type Error = LuarmorError;
+
+        fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+            self.fake.rest_endpoint(path)
+        }
+    }
+    impl Client for Tracked {
+        fn rest(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            self.enter();
+            thread::sleep(Duration::from_millis(20));
+            Ok(self.leave(&request))
+        }
+    }
+    impl AsyncClient for Tracked {
+        async fn rest_async(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            self.enter();
+            for _ in 0..4 {
+                tokio::task::yield_now().await;
+            }
+            Ok(self.leave(&request))
+        }
+    }
+
+    /// Eight specs noted by their index, the third of which fails.
+    fn specs() -> Vec<NewUser<'static>> {
+        (0..8)
+            .map(|i| match i {
+                2 => NewUser::builder().note("fail").build(),
+                i => NewUser::builder().note(format!("user{i}")).build(),
+            })
+            .collect()
+    }
+
+    fn check(tracked: &Tracked, report: &BulkReport) {
+        assert!(!report.is_complete());
+        let failed: Vec<_> = report.failed.iter().map(|x| x.index).collect();
+        assert_eq!(failed, [2]);
+        let created: Vec<_> = report.created.iter().map(|x| x.index).collect();
+        assert_eq!(created, [0, 1, 3, 4, 5, 6, 7]);
+
+        // Each key belongs to the spec at its index
+        let state = tracked.fake.state();
+        let users = &state.project("project").unwrap().users;
+        for BulkCreated { index, user_key } in &report.created {
+            let user = users.iter().find(|x| x.user_key == user_key.as_str());
+            assert_eq!(user.unwrap().note, format!("user{index}"));
+        }
+
+        let peak = tracked.peak.load(Ordering::SeqCst);
+        assert!((2..=3).contains(&peak), "{peak} requests were in flight");
+    }
+
+    #[test]
+    fn bulk_creates_are_bounded_and_ordered() {
+        let tracked = Tracked::new();
+        let client = LuarmorClient::new("secret", tracked.clone());
+        let report = client.create_users_bulk(&"project".parse().unwrap(), specs(), 3);
+        check(&tracked, &report);
+    }
+
+    #[tokio::test]
+    async fn async_bulk_creates_are_bounded_and_ordered() {
+        let tracked = Tracked::new();
+        let client = LuarmorClient::new("secret", tracked.clone());
+        let report = client
+            .create_users_bulk_async(&"project".parse().unwrap(), specs(), 3)
+            .await;
+        check(&tracked, &report);
+    }
+}
@@ -1,229 +1,620 @@
-use api_builder::{error::APIError, AsyncClient, AsyncQuery, Bytes, Client, Query, RestClient, Url};
-use http::{header::AUTHORIZATION, HeaderValue, Request, Response};
+use std::{io::Write, pin::pin};
+
+use api_builder::{
+    AsyncClient, AsyncQuery, Bytes, Client, Endpoint, Query, RestClient, Url,
+    error::{APIError, HeaderError},
+};
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use http::{
+    HeaderValue, Request, Response,
+    header::{AUTHORIZATION, USER_AGENT},
+};
+use serde::de::DeserializeOwned;
 
 use crate::{
-    Luarmor,
+    ApiKey, BulkReport, Clock, ExportError, ExportOptions, ImportOptions, ImportReport, Luarmor,
+    LuarmorError, ProjectHandle, ReconcileAction, ReconcileError, ReconcileOptions, ReconcilePlan,
+    ReconcileReport, SweepAction, SweepOptions, SweepReport, UsersIter,
+    bulk::fan_out,
+    error::decode_response,
+    export::UserWriter,
+    import::Import,
     models::{
-        LuarmorMessage,
+        ProjectId, UnbanToken, UserKey,
         status::{ApiStatus, ApiStatusResponse},
         v3::{
-            keys::{ApiKeyDetails, ApiKeyDetailsResponse, ApiKeyStats, ApiKeyStatsResponse},
+            keys::{
+                ApiKeyDetails, ApiKeyDetailsResponse, ApiKeyStats, ApiKeyStatsNoUsersResponse,
+                ApiKeyStatsResponse,
+            },
             projects::{
                 scripts::UpdateScript,
                 users::{
                     BlacklistUser, CreateUser, CreateUserResponse, DeleteUser, GetUsers,
-                    GetUsersResponse, LinkDiscordId, ResetHwid, UnblacklistUser, UpdateUser, User,
+                    GetUsersResponse, LinkDiscordId, NewUser, ResetHwid, UnblacklistUser,
+                    UpdateUser, User,
                 },
             },
         },
     },
+    pagination::UserPages,
+    reconcile::DesiredUser,
+    sweep::expired,
 };
 
 /// Used to create requests to Luarmor.
 #[derive(Clone, Debug)]
 pub struct LuarmorClient<C> {
-    api_key: String,
+    api_key: ApiKey,
     client: C,
 }
 impl<C> LuarmorClient<C> {
     /// Creates an instance.
-    pub fn new(api_key: String, client: C) -> Self {
-        Self { api_key, client }
+    pub fn new(api_key: impl Into<ApiKey>, client: C) -> Self {
+        Self {
+            api_key: api_key.into(),
+            client,
+        }
+    }
+
+    /// Scopes requests to a single project.
+    pub fn project<'a>(&'a self, project_id: &'a ProjectId) -> ProjectHandle<'a, C> {
+        ProjectHandle::new(self, project_id)
+    }
+
+    /// Applies the headers every request must carry.
+    ///
+    /// Shared by the blocking and async paths so they are always decorated identically.
+    fn decorate(&self, request: &mut Request<Vec<u8>>) -> Result<(), HeaderError> {
+        let headers = request.headers_mut();
+        let mut authorization = HeaderValue::from_str(self.api_key.expose())?;
+        authorization.set_sensitive(true);
+        headers.insert(AUTHORIZATION, authorization);
+        headers
+            .entry(USER_AGENT)
+            .or_insert(HeaderValue::from_static(concat!(
+                "luarmor-rs/",
+                env!("CARGO_PKG_VERSION")
+            )));
+        Ok(())
     }
 }
 impl<C> LuarmorClient<C>
 where
-    C: Client<Error = LuarmorMessage>,
+    C: Client<Error = LuarmorError>,
 {
-    pub fn details(&self) -> Result<ApiKeyDetailsResponse, APIError<C::Error>> {
-        Luarmor(
-            ApiKeyDetails::builder()
-                .api_key(self.api_key.as_str())
-                .build(),
-        )
-        .query(&self.client)
+    /// Sends `endpoint`, unwrapping the [crate::models::LuarmorResponse] it returns.
+    fn execute<E, T>(&self, endpoint: E) -> Result<T, LuarmorError>
+    where
+        E: Endpoint,
+        T: DeserializeOwned,
+    {
+        let endpoint = Luarmor(endpoint);
+        let request = Query::<T, Self>::request(&endpoint, self)?;
+        let response = Query::<T, Self>::send(&endpoint, self, request)?;
+        endpoint.decode(response)
+    }
+
+    pub fn details(&self) -> Result<ApiKeyDetailsResponse, LuarmorError> {
+        self.execute(ApiKeyDetails::builder().api_key(&self.api_key).build())
+    }
+
+    pub fn status(&self) -> Result<ApiStatusResponse, LuarmorError> {
+        let request = Query::<ApiStatusResponse, Self>::request(&ApiStatus, self)?;
+        let response = Query::<ApiStatusResponse, Self>::send(&ApiStatus, self, request)?;
+        decode_response(response)
     }
 
-    pub fn status(&self) -> Result<ApiStatusResponse, APIError<C::Error>> {
-        ApiStatus.query(&self.client)
+    /// Fetches the stats of the API key, including user counts.
+    pub fn stats(&self) -> Result<ApiKeyStatsResponse, LuarmorError> {
+        self.execute(ApiKeyStats::builder().api_key(&self.api_key).build())
     }
 
-    pub fn stats(&self, no_users: bool) -> Result<ApiKeyStatsResponse, APIError<C::Error>> {
-        Luarmor(
+    /// Fetches the stats of the API key, without user counts.
+    pub fn stats_no_users(&self) -> Result<ApiKeyStatsNoUsersResponse, LuarmorError> {
+        self.execute(
             ApiKeyStats::builder()
-                .api_key(self.api_key.as_str())
-                .no_users(no_users)
+                .api_key(&self.api_key)
+                .no_users(true)
                 .build(),
         )
-        .query(&self.client)
     }
 
-    pub fn update_script(&self, payload: UpdateScript<'_>) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query(self)
+    pub fn update_script(&self, payload: UpdateScript<'_>) -> Result<(), LuarmorError> {
+        self.execute(payload)
     }
 
-    pub fn blacklist(&self, payload: BlacklistUser<'_>) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query(self)
+    pub fn blacklist(&self, payload: BlacklistUser<'_>) -> Result<(), LuarmorError> {
+        self.execute(payload)
     }
 
-    pub fn create_user(&self, payload: CreateUser<'_>) -> Result<String, APIError<C::Error>> {
-        let x: Result<CreateUserResponse, APIError<C::Error>> = Luarmor(payload).query(self);
-        x.map(|x| x.user_key)
+    pub fn create_user(&self, payload: CreateUser<'_>) -> Result<UserKey, LuarmorError> {
+        self.execute(payload)
+            .map(|x: CreateUserResponse| x.user_key)
     }
 
-    pub fn delete_user(&self, project_id: &str, user_key: &str) -> Result<(), APIError<C::Error>> {
-        Luarmor(
+    /// Creates a key for every spec within `project_id`, sending up to `concurrency` requests at once.
+    ///
+    /// A failed spec is reported, and doesn't stop the rest.
+    pub fn create_users_bulk<'a>(
+        &self,
+        project_id: &ProjectId,
+        specs: impl IntoIterator<Item = NewUser<'a>, IntoIter: Send>,
+        concurrency: usize,
+    ) -> BulkReport
+    where
+        C: Sync,
+    {
+        BulkReport::new(fan_out(specs, concurrency, |spec| {
+            self.create_user(CreateUser::new(project_id, spec))
+        }))
+    }
+
+    /// Creates a key within `project_id` for every row of `text`.
+    ///
+    /// Invalid rows are reported and skipped, they don't stop the rest of the import.
+    pub fn import_users(
+        &self,
+        project_id: &ProjectId,
+        text: &str,
+        options: ImportOptions,
+    ) -> ImportReport
+    where
+        C: Sync,
+    {
+        let mut import = Import::parse(text, options.format);
+        let bulk = (!options.dry_run)
+            .then(|| self.create_users_bulk(project_id, import.take_specs(), options.concurrency));
+        import.finish(bulk)
+    }
+
+    pub fn delete_user(
+        &self,
+        project_id: &ProjectId,
+        user_key: &UserKey,
+    ) -> Result<(), LuarmorError> {
+        self.execute(
             DeleteUser::builder()
                 .project_id(project_id)
                 .user_key(user_key)
                 .build(),
         )
-        .query(self)
     }
 
-    pub fn users(&self, payload: GetUsers<'_>) -> Result<Vec<User>, APIError<C::Error>> {
-        let x: Result<GetUsersResponse, APIError<LuarmorMessage>> = Luarmor(payload).query(self);
-        x.map(|x| x.users)
+    pub fn users(&self, payload: GetUsers<'_>) -> Result<Vec<User>, LuarmorError> {
+        self.execute(payload).map(|x: GetUsersResponse| x.users)
     }
 
-    pub fn link_discord(&self, payload: LinkDiscordId<'_>) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query(self)
+    /// Lazily walks every user matching `filter`, fetching `page_size` users at a time.
+    ///
+    /// `filter.from` sets where to start, `filter.until` is ignored.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is `0`.
+    pub fn users_iter<'a>(&'a self, filter: GetUsers<'a>, page_size: u32) -> UsersIter<'a, C> {
+        UsersIter::new(self, filter, page_size)
     }
 
-    pub fn reset_hwid(&self, payload: ResetHwid<'_>) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query(self)
+    /// Writes every user within `project_id` to `writer`, returning how many were written.
+    pub fn export_users<W: Write>(
+        &self,
+        project_id: &ProjectId,
+        writer: W,
+        options: ExportOptions,
+    ) -> Result<usize, ExportError> {
+        let filter = GetUsers::builder().project_id(project_id).build();
+        let mut users = self.users_iter(filter, options.page_size);
+
+        let mut writer = UserWriter::new(writer, options)?;
+        users.try_for_each(|user| writer.write(&user?).map_err(ExportError::from))?;
+        let rows = writer.rows();
+        writer.finish()?;
+        Ok(rows)
+    }
+
+    /// Fetches every user within `project_id` and works out the changes needed to match `desired`.
+    pub fn plan_reconcile(
+        &self,
+        project_id: &ProjectId,
+        desired: impl IntoIterator<Item = DesiredUser>,
+        options: &ReconcileOptions,
+    ) -> Result<ReconcilePlan, ReconcileError> {
+        let filter = GetUsers::builder().project_id(project_id).build();
+        let current = self
+            .users_iter(filter, options.page_size)
+            .collect::<Result<Vec<_>, _>>()?;
+        ReconcilePlan::new(project_id, desired, &current, options)
+    }
+
+    /// Applies `plan` an action at a time, only if `confirm` returns `true`, e.g. after printing the plan and prompting.
+    ///
+    /// A failed action doesn't stop the rest of the plan.
+    pub fn apply_reconcile(
+        &self,
+        plan: &ReconcilePlan,
+        confirm: impl FnOnce(&ReconcilePlan) -> bool,
+    ) -> Option<ReconcileReport> {
+        if !plan.is_empty() && !confirm(plan) {
+            return None;
+        }
+
+        let mut report = ReconcileReport::default();
+        for (index, action) in plan.actions.iter().enumerate() {
+            let result = match action {
+                ReconcileAction::Create(x) => self.create_user(x.clone()).map(Some),
+                ReconcileAction::Update(x) => self.update_user(x.clone()).map(|_| None),
+                ReconcileAction::Unblacklist { unban_token, .. } => self
+                    .unblacklist(&plan.project_id, unban_token)
+                    .map(|_| None),
+                ReconcileAction::Blacklist(x) => self.blacklist(x.clone()).map(|_| None),
+                ReconcileAction::Delete(user_key) => {
+                    self.delete_user(&plan.project_id, user_key).map(|_| None)
+                }
+            };
+            report.push(index, result);
+        }
+        Some(report)
+    }
+
+    /// Deletes or blacklists the keys within `project_id` which expired more than [SweepOptions::grace] ago, according to `clock`.
+    ///
+    /// Keys which never expire are never swept.
+    pub fn sweep_expired(
+        &self,
+        project_id: &ProjectId,
+        options: &SweepOptions,
+        clock: &impl Clock,
+    ) -> Result<SweepReport, LuarmorError> {
+        // Fetch everything first, removing keys would shift the pages
+        let filter = GetUsers::builder().project_id(project_id).build();
+        let users = self
+            .users_iter(filter, options.page_size)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (keys, deferred) = expired(users, clock.now(), options);
+        let mut report = SweepReport {
+            deferred,
+            ..Default::default()
+        };
+        for key in keys {
+            let result = match (&options.action, options.dry_run) {
+                (_, true) => Ok(()),
+                (SweepAction::Delete, false) => self.delete_user(project_id, &key.user_key),
+                (SweepAction::Blacklist { reason }, false) => self.blacklist(BlacklistUser {
+                    project_id: project_id.into(),
+                    user_key: (&key.user_key).into(),
+                    ban_reason: reason.as_deref().map(Into::into),
+                    ban_expire: None,
+                }),
+            };
+            report.push(key, result);
+        }
+        Ok(report)
+    }
+
+    pub fn link_discord(&self, payload: LinkDiscordId<'_>) -> Result<(), LuarmorError> {
+        self.execute(payload)
+    }
+
+    pub fn reset_hwid(&self, payload: ResetHwid<'_>) -> Result<(), LuarmorError> {
+        self.execute(payload)
     }
 
     pub fn unblacklist(
         &self,
-        project_id: &str,
-        unban_token: &str,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(
+        project_id: &ProjectId,
+        unban_token: &UnbanToken,
+    ) -> Result<(), LuarmorError> {
+        self.execute(
             UnblacklistUser::builder()
                 .project_id(project_id)
                 .unban_token(unban_token)
                 .build(),
         )
-        .query(&self.client)
     }
 
-    pub fn update_user(&self, payload: UpdateUser<'_>) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query(self)
+    pub fn update_user(&self, payload: UpdateUser<'_>) -> Result<(), LuarmorError> {
+        self.execute(payload)
     }
 }
 impl<C> LuarmorClient<C>
 where
-    C: AsyncClient<Error = LuarmorMessage> + Sync,
+    C: AsyncClient<Error = LuarmorError> + Sync,
 {
-    pub async fn details_async(&self) -> Result<ApiKeyDetailsResponse, APIError<C::Error>> {
-        Luarmor(
-            ApiKeyDetails::builder()
-                .api_key(self.api_key.as_str())
-                .build(),
-        )
-        .query_async(&self.client)
-        .await
+    /// Sends `endpoint`, unwrapping the [crate::models::LuarmorResponse] it returns.
+    async fn execute_async<E, T>(&self, endpoint: E) -> Result<T, LuarmorError>
+    where
+        E: Endpoint + Sync,
+        T: DeserializeOwned,
+    {
+        let endpoint = Luarmor(endpoint);
+        let request = AsyncQuery::<T, Self>::request_async(&endpoint, self).await?;
+        let response = AsyncQuery::<T, Self>::send_async(&endpoint, self, request).await?;
+        endpoint.decode(response)
     }
 
-    pub async fn status_async(&self) -> Result<ApiStatusResponse, APIError<C::Error>> {
-        ApiStatus.query_async(&self.client).await
+    pub async fn details_async(&self) -> Result<ApiKeyDetailsResponse, LuarmorError> {
+        self.execute_async(ApiKeyDetails::builder().api_key(&self.api_key).build())
+            .await
     }
 
-    pub async fn stats_async(
-        &self,
-        no_users: bool,
-    ) -> Result<ApiKeyStatsResponse, APIError<C::Error>> {
-        Luarmor(
+    pub async fn status_async(&self) -> Result<ApiStatusResponse, LuarmorError> {
+        let request =
+            AsyncQuery::<ApiStatusResponse, Self>::request_async(&ApiStatus, self).await?;
+        let response =
+            AsyncQuery::<ApiStatusResponse, Self>::send_async(&ApiStatus, self, request).await?;
+        decode_response(response)
+    }
+
+    /// Fetches the stats of the API key, including user counts.
+    pub async fn stats_async(&self) -> Result<ApiKeyStatsResponse, LuarmorError> {
+        self.execute_async(ApiKeyStats::builder().api_key(&self.api_key).build())
+            .await
+    }
+
+    /// Fetches the stats of the API key, without user counts.
+    pub async fn stats_no_users_async(&self) -> Result<ApiKeyStatsNoUsersResponse, LuarmorError> {
+        self.execute_async(
             ApiKeyStats::builder()
-                .api_key(self.api_key.as_str())
-                .no_users(no_users)
+                .api_key(&self.api_key)
+                .no_users(true)
                 .build(),
         )
-        .query_async(&self.client)
         .await
     }
 
-    pub async fn update_script_async(
-        &self,
-        payload: UpdateScript<'_>,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query_async(self).await
+    pub async fn update_script_async(&self, payload: UpdateScript<'_>) -> Result<(), LuarmorError> {
+        self.execute_async(payload).await
     }
 
-    pub async fn blacklist_async(
-        &self,
-        payload: BlacklistUser<'_>,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query_async(self).await
+    pub async fn blacklist_async(&self, payload: BlacklistUser<'_>) -> Result<(), LuarmorError> {
+        self.execute_async(payload).await
     }
 
     pub async fn create_user_async(
         &self,
         payload: CreateUser<'_>,
-    ) -> Result<CreateUserResponse, APIError<C::Error>> {
-        Luarmor(payload).query_async(self).await
+    ) -> Result<CreateUserResponse, LuarmorError> {
+        self.execute_async(payload).await
+    }
+
+    /// Creates a key for every spec within `project_id`, sending up to `concurrency` requests at once.
+    ///
+    /// A failed spec is reported, and doesn't stop the rest.
+    pub async fn create_users_bulk_async<'a>(
+        &self,
+        project_id: &ProjectId,
+        specs: impl IntoIterator<Item = NewUser<'a>>,
+        concurrency: usize,
+    ) -> BulkReport {
+        let results = stream::iter(specs.into_iter().enumerate())
+            .map(|(index, spec)| async move {
+                let payload = CreateUser::new(project_id, spec);
+                (
+                    index,
+                    self.create_user_async(payload).await.map(|x| x.user_key),
+                )
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        BulkReport::new(results)
+    }
+
+    /// Creates a key within `project_id` for every row of `text`.
+    ///
+    /// Invalid rows are reported and skipped, they don't stop the rest of the import.
+    pub async fn import_users_async(
+        &self,
+        project_id: &ProjectId,
+        text: &str,
+        options: ImportOptions,
+    ) -> ImportReport {
+        let mut import = Import::parse(text, options.format);
+        let bulk = match options.dry_run {
+            true => None,
+            false => Some(
+                self.create_users_bulk_async(project_id, import.take_specs(), options.concurrency)
+                    .await,
+            ),
+        };
+        import.finish(bulk)
     }
 
     pub async fn delete_user_async(
         &self,
-        project_id: &str,
-        user_key: &str,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(
+        project_id: &ProjectId,
+        user_key: &UserKey,
+    ) -> Result<(), LuarmorError> {
+        self.execute_async(
             DeleteUser::builder()
                 .project_id(project_id)
                 .user_key(user_key)
                 .build(),
         )
-        .query_async(self)
         .await
     }
 
-    pub async fn users_async(
+    pub async fn users_async(&self, payload: GetUsers<'_>) -> Result<Vec<User>, LuarmorError> {
+        self.execute_async(payload)
+            .await
+            .map(|x: GetUsersResponse| x.users)
+    }
+
+    /// Lazily walks every user matching `filter`, fetching `page_size` users at a time.
+    ///
+    /// `filter.from` sets where to start, `filter.until` is ignored. Ends after the first error.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is `0`.
+    pub fn users_stream<'a>(
+        &'a self,
+        filter: GetUsers<'a>,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<User, LuarmorError>> + Send + 'a {
+        stream::unfold(
+            UserPages::new(filter, page_size),
+            move |mut pages| async move {
+                loop {
+                    if let Some(user) = pages.pop() {
+                        return Some((Ok(user), pages));
+                    }
+
+                    let request = pages.next_request()?;
+                    if let Err(err) = pages.push(self.users_async(request).await) {
+                        return Some((Err(err), pages));
+                    }
+                }
+            },
+        )
+    }
+
+    /// Writes every user within `project_id` to `writer`, returning how many were written.
+    ///
+    /// NOTE: `writer` is blocking, consider writing to a buffer if that is an issue.
+    pub async fn export_users_async<W: Write>(
         &self,
-        payload: GetUsers<'_>,
-    ) -> Result<Vec<User>, APIError<C::Error>> {
-        let x: Result<GetUsersResponse, APIError<LuarmorMessage>> =
-            Luarmor(payload).query_async(self).await;
-        x.map(|x| x.users)
+        project_id: &ProjectId,
+        writer: W,
+        options: ExportOptions,
+    ) -> Result<usize, ExportError> {
+        let filter = GetUsers::builder().project_id(project_id).build();
+        let users = self.users_stream(filter, options.page_size);
+        let mut users = pin!(users);
+
+        let mut writer = UserWriter::new(writer, options)?;
+        while let Some(user) = users.next().await {
+            writer.write(&user?)?;
+        }
+        let rows = writer.rows();
+        writer.finish()?;
+        Ok(rows)
     }
 
-    pub async fn link_discord_async(
+    /// Fetches every user within `project_id` and works out the changes needed to match `desired`.
+    pub async fn plan_reconcile_async(
         &self,
-        payload: LinkDiscordId<'_>,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query_async(self).await
+        project_id: &ProjectId,
+        desired: impl IntoIterator<Item = DesiredUser>,
+        options: &ReconcileOptions,
+    ) -> Result<ReconcilePlan, ReconcileError> {
+        let filter = GetUsers::builder().project_id(project_id).build();
+        let current: Vec<_> = self
+            .users_stream(filter, options.page_size)
+            .try_collect()
+            .await?;
+        ReconcilePlan::new(project_id, desired, &current, options)
     }
 
-    pub async fn reset_hwid_async(&self, payload: ResetHwid<'_>) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query_async(self).await
+    /// Applies `plan` an action at a time, only if `confirm` returns `true`, e.g. after printing the plan and prompting.
+    ///
+    /// A failed action doesn't stop the rest of the plan.
+    pub async fn apply_reconcile_async(
+        &self,
+        plan: &ReconcilePlan,
+        confirm: impl FnOnce(&ReconcilePlan) -> bool,
+    ) -> Option<ReconcileReport> {
+        if !plan.is_empty() && !confirm(plan) {
+            return None;
+        }
+
+        let mut report = ReconcileReport::default();
+        for (index, action) in plan.actions.iter().enumerate() {
+            let result = match action {
+                ReconcileAction::Create(x) => self
+                    .create_user_async(x.clone())
+                    .await
+                    .map(|x| Some(x.user_key)),
+                ReconcileAction::Update(x) => self.update_user_async(x.clone()).await.map(|_| None),
+                ReconcileAction::Unblacklist { unban_token, .. } => self
+                    .unblacklist_async(&plan.project_id, unban_token)
+                    .await
+                    .map(|_| None),
+                ReconcileAction::Blacklist(x) => {
+                    self.blacklist_async(x.clone()).await.map(|_| None)
+                }
+                ReconcileAction::Delete(user_key) => self
+                    .delete_user_async(&plan.project_id, user_key)
+                    .await
+                    .map(|_| None),
+            };
+            report.push(index, result);
+        }
+        Some(report)
+    }
+
+    /// Deletes or blacklists the keys within `project_id` which expired more than [SweepOptions::grace] ago, according to `clock`.
+    ///
+    /// Keys which never expire are never swept.
+    pub async fn sweep_expired_async(
+        &self,
+        project_id: &ProjectId,
+        options: &SweepOptions,
+        clock: &impl Clock,
+    ) -> Result<SweepReport, LuarmorError> {
+        // Fetch everything first, removing keys would shift the pages
+        let filter = GetUsers::builder().project_id(project_id).build();
+        let users: Vec<_> = self
+            .users_stream(filter, options.page_size)
+            .try_collect()
+            .await?;
+
+        let (keys, deferred) = expired(users, clock.now(), options);
+        let mut report = SweepReport {
+            deferred,
+            ..Default::default()
+        };
+        for key in keys {
+            let result = match (&options.action, options.dry_run) {
+                (_, true) => Ok(()),
+                (SweepAction::Delete, false) => {
+                    self.delete_user_async(project_id, &key.user_key).await
+                }
+                (SweepAction::Blacklist { reason }, false) => {
+                    self.blacklist_async(BlacklistUser {
+                        project_id: project_id.into(),
+                        user_key: (&key.user_key).into(),
+                        ban_reason: reason.as_deref().map(Into::into),
+                        ban_expire: None,
+                    })
+                    .await
+                }
+            };
+            report.push(key, result);
+        }
+        Ok(report)
+    }
+
+    pub async fn link_discord_async(&self, payload: LinkDiscordId<'_>) -> Result<(), LuarmorError> {
+        self.execute_async(payload).await
+    }
+
+    pub async fn reset_hwid_async(&self, payload: ResetHwid<'_>) -> Result<(), LuarmorError> {
+        self.execute_async(payload).await
     }
 
     pub async fn unblacklist_async(
         &self,
-        project_id: &str,
-        unban_token: &str,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(
+        project_id: &ProjectId,
+        unban_token: &UnbanToken,
+    ) -> Result<(), LuarmorError> {
+        self.execute_async(
             UnblacklistUser::builder()
                 .project_id(project_id)
                 .unban_token(unban_token)
                 .build(),
         )
-        .query_async(&self.client)
         .await
     }
 
-    pub async fn update_user_async(
-        &self,
-        payload: UpdateUser<'_>,
-    ) -> Result<(), APIError<C::Error>> {
-        Luarmor(payload).query_async(self).await
+    pub async fn update_user_async(&self, payload: UpdateUser<'_>) -> Result<(), LuarmorError> {
+        self.execute_async(payload).await
     }
 }
 
-// Adds `api_key` Authorization header
+// Decorates each request before handing it to the inner client
 impl<C> RestClient for LuarmorClient<C>
 where
     C: RestClient,
@@ -242,9 +633,7 @@ where
         &self,
         mut request: Request<Vec<u8>>,
     ) -> Result<Response<Bytes>, APIError<Self::Error>> {
-        request
-            .headers_mut()
-            .append(AUTHORIZATION, HeaderValue::from_str(self.api_key.as_str())?);
+        self.decorate(&mut request)?;
         self.client.rest(request)
     }
 }
@@ -254,10 +643,241 @@ where
 {
     fn rest_async(
         &self,
-        request: Request<Vec<u8>>,
-    ) -> impl Future<
-        Output = Result<Response<Bytes>, APIError<Self::Error>>,
-    > + Send {
-        self.client.rest_async(request)
+        mut request: Request<Vec<u8>>,
+    ) -> impl Future<Output = Result<Response<Bytes>, APIError<Self::Error>>> + Send {
+        let response = self
+            .decorate(&mut request)
+            .map(|()| self.client.rest_async(request));
+        async move { response?.await }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use http::{HeaderMap, StatusCode};
+
+    use super::*;
+    use crate::{
+        models::{DiscordId, ScriptId},
+        route::Route,
+    };
+
+    const API_KEY: &str = "secret";
+
+    type Sent = (Option<Route>, HeaderMap);
+
+    /// Records the headers of every request, answering each with a bare success.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<Sent>>>);
+    impl Recorder {
+        fn respond(&self, request: &Request<Vec<u8>>) -> Response<Bytes> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((Route::from_request(request), request.headers().clone()));
+
+            let mut response = Response::new(Bytes::from_static(
+                br#"{"success":true,"message":"Success!","user_key":"abc","users":[]}"#,
+            ));
+            *response.status_mut() = StatusCode::OK;
+            response
+        }
+
+        /// Asserts every request was authorized, returning the routes they targeted.
+        fn routes(&self) -> Vec<Route> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(route, headers)| {
+                    assert_eq!(headers[AUTHORIZATION], API_KEY, "{route:?}");
+                    assert!(headers[AUTHORIZATION].is_sensitive());
+                    assert!(headers.contains_key(USER_AGENT));
+                    route.expect("unknown route")
+                })
+                .collect()
+        }
+    }
+    impl RestClient for Recorder {
+        type Error = LuarmorError;
+
+        fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+            Ok(Url::parse("https://api.luarmor.net")?.join(path)?)
+        }
+    }
+    impl Client for Recorder {
+        fn rest(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond(&request))
+        }
+    }
+    impl AsyncClient for Recorder {
+        async fn rest_async(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond(&request))
+        }
+    }
+
+    /// One of each route, in the order the tests send them.
+    fn every_route() -> Vec<Route> {
+        vec![
+            Route::KeyDetails,
+            Route::Status,
+            Route::KeyStats,
+            Route::KeyStats,
+            Route::UpdateScript,
+            Route::BlacklistUser,
+            Route::CreateUser,
+            Route::DeleteUser,
+            Route::GetUsers { filtered: false },
+            Route::LinkDiscord,
+            Route::ResetHwid,
+            Route::UnblacklistUser,
+            Route::UpdateUser,
+        ]
+    }
+
+    struct Ids {
+        project_id: ProjectId,
+        script_id: ScriptId,
+        user_key: UserKey,
+        discord_id: DiscordId,
+        unban_token: UnbanToken,
+    }
+    impl Default for Ids {
+        fn default() -> Self {
+            Self {
+                project_id: "project".parse().unwrap(),
+                script_id: "script".parse().unwrap(),
+                user_key: "key".parse().unwrap(),
+                discord_id: "1".parse().unwrap(),
+                unban_token: "a".repeat(32).parse().unwrap(),
+            }
+        }
+    }
+
+    // Responses are not what each endpoint expects, only the requests matter
+    #[test]
+    fn every_endpoint_is_authorized() {
+        let recorder = Recorder::default();
+        let client = LuarmorClient::new(API_KEY, recorder.clone());
+        let ids = Ids::default();
+
+        let _ = client.details();
+        let _ = client.status();
+        let _ = client.stats();
+        let _ = client.stats_no_users();
+        let _ = client.update_script(
+            UpdateScript::builder()
+                .project_id(&ids.project_id)
+                .script_id(&ids.script_id)
+                .script("print()")
+                .build(),
+        );
+        let _ = client.blacklist(
+            BlacklistUser::builder()
+                .project_id(&ids.project_id)
+                .user_key(&ids.user_key)
+                .build(),
+        );
+        let _ = client.create_user(CreateUser::builder().project_id(&ids.project_id).build());
+        let _ = client.delete_user(&ids.project_id, &ids.user_key);
+        let _ = client.users(GetUsers::builder().project_id(&ids.project_id).build());
+        let _ = client.link_discord(
+            LinkDiscordId::builder()
+                .project_id(&ids.project_id)
+                .user_key(&ids.user_key)
+                .discord_id(&ids.discord_id)
+                .build(),
+        );
+        let _ = client.reset_hwid(
+            ResetHwid::builder()
+                .project_id(&ids.project_id)
+                .user_key(&ids.user_key)
+                .build(),
+        );
+        let _ = client.unblacklist(&ids.project_id, &ids.unban_token);
+        let _ = client.update_user(
+            UpdateUser::builder()
+                .project_id(&ids.project_id)
+                .user_key(&ids.user_key)
+                .build(),
+        );
+
+        assert_eq!(recorder.routes(), every_route());
+    }
+
+    #[tokio::test]
+    async fn every_endpoint_is_authorized_async() {
+        let recorder = Recorder::default();
+        let client = LuarmorClient::new(API_KEY, recorder.clone());
+        let ids = Ids::default();
+
+        let _ = client.details_async().await;
+        let _ = client.status_async().await;
+        let _ = client.stats_async().await;
+        let _ = client.stats_no_users_async().await;
+        let _ = client
+            .update_script_async(
+                UpdateScript::builder()
+                    .project_id(&ids.project_id)
+                    .script_id(&ids.script_id)
+                    .script("print()")
+                    .build(),
+            )
+            .await;
+        let _ = client
+            .blacklist_async(
+                BlacklistUser::builder()
+                    .project_id(&ids.project_id)
+                    .user_key(&ids.user_key)
+                    .build(),
+            )
+            .await;
+        let _ = client
+            .create_user_async(CreateUser::builder().project_id(&ids.project_id).build())
+            .await;
+        let _ = client
+            .delete_user_async(&ids.project_id, &ids.user_key)
+            .await;
+        let _ = client
+            .users_async(GetUsers::builder().project_id(&ids.project_id).build())
+            .await;
+        let _ = client
+            .link_discord_async(
+                LinkDiscordId::builder()
+                    .project_id(&ids.project_id)
+                    .user_key(&ids.user_key)
+                    .discord_id(&ids.discord_id)
+                    .build(),
+            )
+            .await;
+        let _ = client
+            .reset_hwid_async(
+                ResetHwid::builder()
+                    .project_id(&ids.project_id)
+                    .user_key(&ids.user_key)
+                    .build(),
+            )
+            .await;
+        let _ = client
+            .unblacklist_async(&ids.project_id, &ids.unban_token)
+            .await;
+        let _ = client
+            .update_user_async(
+                UpdateUser::builder()
+                    .project_id(&ids.project_id)
+                    .user_key(&ids.user_key)
+                    .build(),
+            )
+            .await;
+
+        assert_eq!(recorder.routes(), every_route());
     }
 }
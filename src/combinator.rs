@@ -5,11 +5,57 @@ use api_builder::{
     impl_query_async,
 };
 use http::Response;
-use serde::de::DeserializeOwned;
+use serde::de::{DeserializeOwned, IgnoredAny};
 
-use crate::models::{LuarmorMessage, LuarmorResponse};
+use crate::{LuarmorError, error::decode, models::LuarmorResponse};
 
 pub struct Luarmor<E>(pub E);
+impl<E> Luarmor<E>
+where
+    E: Endpoint,
+{
+    /// Unwraps the [LuarmorResponse] of `response`.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        response: Response<Bytes>,
+    ) -> Result<T, LuarmorError> {
+        let status = response.status();
+        if response.body().is_empty() && !status.is_success() && !self.0.ignore_errors() {
+            return Err(LuarmorError::Http {
+                status,
+                body: response.into_body(),
+            });
+        }
+
+        // Check the envelope first, so a malformed payload is reported as such
+        let lrm_response = match decode::<LuarmorResponse<IgnoredAny>>(response.body()) {
+            Ok(x) => x,
+            Err(_) if !status.is_success() && !self.0.ignore_errors() => {
+                return Err(LuarmorError::Http {
+                    status,
+                    body: response.into_body(),
+                });
+            }
+            Err(err) => return Err(err),
+        };
+        if !lrm_response.success {
+            return Err(LuarmorError::Api {
+                status,
+                message: lrm_response.message,
+            });
+        }
+
+        // The payload sits alongside `success` and `message`, types such as `()` can only be read when flattened
+        decode::<T>(response.body()).or_else(|err| {
+            match serde_json::from_slice::<LuarmorResponse<T>>(response.body()) {
+                Ok(LuarmorResponse {
+                    data: Some(data), ..
+                }) => Ok(data),
+                _ => Err(err),
+            }
+        })
+    }
+}
 impl<E> Deref for Luarmor<E> {
     type Target = E;
 
@@ -21,51 +67,27 @@ impl<E, T, C> Query<T, C> for Luarmor<E>
 where
     E: Endpoint,
     T: DeserializeOwned,
-    C: Client<Error = LuarmorMessage>,
+    C: Client<Error = LuarmorError>,
 {
     impl_query!("request");
     impl_query!("send");
     impl_query!("query");
 
     fn finalise(&self, response: Response<Bytes>) -> Result<T, APIError<C::Error>> {
-        if response.body().is_empty() && !response.status().is_success() && !self.0.ignore_errors()
-        {
-            return Err(response)?;
-        }
-
-        let lrm_response = serde_json::from_slice::<LuarmorResponse<T>>(response.body())?;
-        if !lrm_response.success {
-            Err(lrm_response.message)?
-        } else if let Some(data) = lrm_response.data {
-            Ok(data)
-        } else {
-            Err(response)?
-        }
+        Ok(self.decode(response)?)
     }
 }
 impl<E, T, C> AsyncQuery<T, C> for Luarmor<E>
 where
     E: Endpoint + Sync,
     T: DeserializeOwned,
-    C: AsyncClient<Error = LuarmorMessage> + Sync,
+    C: AsyncClient<Error = LuarmorError> + Sync,
 {
     impl_query_async!("request");
     impl_query_async!("send");
     impl_query_async!("query");
 
     async fn finalise_async(&self, response: Response<Bytes>) -> Result<T, APIError<C::Error>> {
-        if response.body().is_empty() && !response.status().is_success() && !self.0.ignore_errors()
-        {
-            return Err(response)?;
-        }
-
-        let lrm_response = serde_json::from_slice::<LuarmorResponse<T>>(response.body())?;
-        if !lrm_response.success {
-            Err(lrm_response.message)?
-        } else if let Some(data) = lrm_response.data {
-            Ok(data)
-        } else {
-            Err(response)?
-        }
+        Ok(self.decode(response)?)
     }
 }
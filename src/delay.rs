@@ -0,0 +1,101 @@
+// A runtime-agnostic async delay, so the async client layers don't tie the crate to a specific runtime.
+//
+// A single background thread keeps a queue of deadlines and wakes each future once its deadline passes.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    pin::Pin,
+    sync::{Condvar, Mutex, OnceLock},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+struct Entry {
+    deadline: Instant,
+    waker: Waker,
+}
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+impl Eq for Entry {}
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+#[derive(Default)]
+struct Timer {
+    queue: Mutex<BinaryHeap<Reverse<Entry>>>,
+    condvar: Condvar,
+}
+impl Timer {
+    fn get() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+        TIMER.get_or_init(|| {
+            thread::Builder::new()
+                .name("luarmor-timer".into())
+                .spawn(|| Timer::get().run())
+                .expect("failed to spawn timer thread");
+            Timer::default()
+        })
+    }
+
+    fn register(&self, deadline: Instant, waker: Waker) {
+        self.queue
+            .lock()
+            .unwrap()
+            .push(Reverse(Entry { deadline, waker }));
+        self.condvar.notify_one();
+    }
+
+    fn run(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            queue = match queue.peek() {
+                None => self.condvar.wait(queue).unwrap(),
+                Some(Reverse(entry)) if entry.deadline <= now => {
+                    queue.pop().unwrap().0.waker.wake();
+                    queue
+                }
+                Some(Reverse(entry)) => {
+                    let timeout = entry.deadline - now;
+                    self.condvar.wait_timeout(queue, timeout).unwrap().0
+                }
+            };
+        }
+    }
+}
+
+/// A future which completes once `duration` has elapsed.
+pub(crate) struct Delay {
+    deadline: Instant,
+}
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if Instant::now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            Timer::get().register(self.deadline, cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+pub(crate) fn sleep(duration: Duration) -> Delay {
+    Delay {
+        deadline: Instant::now() + duration,
+    }
+}
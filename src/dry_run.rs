@@ -0,0 +1,206 @@
+use std::{
+    fmt, mem,
+    sync::{Arc, Mutex},
+};
+
+use api_builder::{AsyncClient, Bytes, Client, RestClient, Url, error::APIError};
+use http::{Method, Request, Response, StatusCode, header::CONTENT_TYPE};
+use serde_json::{Value, json};
+
+use crate::{models::LuarmorMessage, route::Route, util::random_token};
+
+/// A request intercepted by [DryRun].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DryRunRequest {
+    /// `None` if the endpoint isn't known to this crate.
+    pub route: Option<Route>,
+    pub method: Method,
+    /// The path and query string.
+    pub path: String,
+    pub body: Bytes,
+}
+impl DryRunRequest {
+    /// The body as JSON, if it is any.
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+}
+impl fmt::Display for DryRunRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)?;
+        if !self.body.is_empty() {
+            write!(f, " {}", String::from_utf8_lossy(&self.body))?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a client, only letting reads (see [Route::is_idempotent]) through.
+///
+/// Everything else is recorded and answered with a synthetic success, e.g. a random `user_key` for
+/// [crate::models::v3::projects::users::CreateUser], so workflows can run end to end without changing anything.
+/// Every clone shares the same recorded requests.
+///
+/// Place it above [crate::Retry] and [crate::RateLimited], e.g. `LuarmorClient<DryRun<Retry<RateLimited<C>>>>`,
+/// so intercepted requests are never retried, charged or delayed.
+#[derive(Clone, Debug)]
+pub struct DryRun<C> {
+    client: C,
+    requests: Arc<Mutex<Vec<DryRunRequest>>>,
+}
+impl<C> DryRun<C> {
+    /// Creates an instance.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            requests: Arc::default(),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// The requests intercepted so far, in the order they were sent.
+    pub fn requests(&self) -> Vec<DryRunRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Takes the requests intercepted so far, leaving none behind.
+    pub fn take_requests(&self) -> Vec<DryRunRequest> {
+        mem::take(&mut self.requests.lock().unwrap())
+    }
+
+    /// Whether `request` is a read, and should be sent as is.
+    fn passes_through<B>(request: &Request<B>) -> bool {
+        Route::from_request(request).is_some_and(|x| x.is_idempotent())
+    }
+
+    /// Records `request` and builds its synthetic response.
+    fn intercept(&self, request: Request<Vec<u8>>) -> Response<Bytes> {
+        let route = Route::from_request(&request);
+        let (parts, body) = request.into_parts();
+        self.requests.lock().unwrap().push(DryRunRequest {
+            route,
+            method: parts.method,
+            path: parts
+                .uri
+                .path_and_query()
+                .map_or_else(|| parts.uri.path().to_string(), |x| x.to_string()),
+            body: body.into(),
+        });
+
+        let (message, mut data) = match route {
+            Some(Route::CreateUser) => (
+                LuarmorMessage::Success,
+                json!({ "user_key": random_token(32) }),
+            ),
+            Some(Route::UpdateUser) => (LuarmorMessage::EditSuccess, json!({})),
+            Some(Route::DeleteUser) => (LuarmorMessage::UserDeleted, json!({})),
+            Some(Route::ResetHwid) => (LuarmorMessage::SuccessReset, json!({})),
+            Some(Route::LinkDiscord) => (LuarmorMessage::DiscordIdSuccess, json!({})),
+            _ => (LuarmorMessage::Success, json!({})),
+        };
+        data["success"] = true.into();
+        data["message"] = message.as_str().into();
+
+        let mut response = Response::new(Bytes::from(data.to_string()));
+        *response.status_mut() = StatusCode::OK;
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        response
+    }
+}
+impl<C> RestClient for DryRun<C>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        self.client.rest_endpoint(path)
+    }
+}
+impl<C> Client for DryRun<C>
+where
+    C: Client,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        if !Self::passes_through(&request) {
+            return Ok(self.intercept(request));
+        }
+        self.client.rest(request)
+    }
+}
+impl<C> AsyncClient for DryRun<C>
+where
+    C: AsyncClient + Sync,
+    C::Error: Send,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        if !Self::passes_through(&request) {
+            return Ok(self.intercept(request));
+        }
+        self.client.rest_async(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        LuarmorClient, RateLimited, RateLimiter,
+        models::v3::projects::users::NewUser,
+        testing::{FakeLuarmor, FakeProject},
+    };
+
+    type Limited = DryRun<RateLimited<FakeLuarmor>>;
+
+    /// A dry run over a limiter which can afford a single request.
+    fn dry_run() -> (LuarmorClient<Limited>, Limited, RateLimiter) {
+        let fake = FakeLuarmor::new("secret").with_project(FakeProject::new("project", "Project"));
+        let limiter = RateLimiter::new(0.001, 1);
+        let client = DryRun::new(RateLimited::new(fake, limiter.clone()));
+        (
+            LuarmorClient::new("secret", client.clone()),
+            client,
+            limiter,
+        )
+    }
+
+    #[test]
+    fn intercepted_requests_are_not_charged() {
+        let (client, dry_run, limiter) = dry_run();
+        let project_id = "project".parse().unwrap();
+        for _ in 0..3 {
+            client
+                .project(&project_id)
+                .create_user(NewUser::default())
+                .unwrap();
+        }
+
+        assert_eq!(dry_run.requests().len(), 3);
+        assert!(limiter.reserve(&Request::new(())).is_zero());
+    }
+
+    #[tokio::test]
+    async fn intercepted_requests_are_not_charged_async() {
+        let (client, dry_run, limiter) = dry_run();
+        let project_id = "project".parse().unwrap();
+        for _ in 0..3 {
+            client
+                .project(&project_id)
+                .create_user_async(NewUser::default())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(dry_run.requests().len(), 3);
+        assert!(limiter.reserve(&Request::new(())).is_zero());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{error::Error, fmt, sync::Arc};
+
+use api_builder::{APIClientError, Bytes, error::APIError, error::APIErrorKind};
+use http::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::{models::LuarmorMessage, secret::REDACTED};
+
+/// Errors returned by [crate::LuarmorClient].
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum LuarmorError {
+    /// The request could not be built or sent, or no response was received.
+    #[error("failed to send the request: {0}")]
+    Transport(#[source] Arc<dyn Error + Send + Sync>),
+    /// The server responded with an error status, without a message from Luarmor.
+    #[error("the server responded with {status}")]
+    Http { status: StatusCode, body: Bytes },
+    /// The response body could not be decoded.
+    #[error("failed to decode the response at `{path}`: {source}")]
+    Decode {
+        body: Bytes,
+        /// The path to the value which failed to decode, e.g. `users[0].status`.
+        path: String,
+        source: Arc<serde_json::Error>,
+    },
+    /// Luarmor rejected the request, i.e. `success` was `false`.
+    #[error("{message} ({status})")]
+    Api {
+        status: StatusCode,
+        message: LuarmorMessage,
+    },
+}
+impl APIClientError for LuarmorError {}
+impl LuarmorError {
+    /// The status code of the response, if one was received.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Http { status, .. } | Self::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// The message Luarmor rejected the request with, if any.
+    pub fn message(&self) -> Option<&LuarmorMessage> {
+        match self {
+            Self::Api { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+}
+impl From<APIError<LuarmorError>> for LuarmorError {
+    fn from(value: APIError<LuarmorError>) -> Self {
+        match value.kind() {
+            APIErrorKind::Client(err) => err.clone(),
+            APIErrorKind::Response(response) => Self::Http {
+                status: response.status(),
+                body: response.body().clone(),
+            },
+            _ => Self::Transport(Arc::new(TransportError(value))),
+        }
+    }
+}
+
+/// Replaces the API key in any `/v3/keys/{key}` path within `text`.
+fn redact_api_key(text: &str) -> String {
+    const KEYS: &str = "/v3/keys/";
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(KEYS) {
+        let (before, after) = rest.split_at(start + KEYS.len());
+        let end = after
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+            .unwrap_or(after.len());
+        redacted.push_str(before);
+        redacted.push_str(REDACTED);
+        rest = &after[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// A failure to send a request, formatted without the API key.
+///
+/// HTTP client errors, e.g. `reqwest::Error`, include the URL, which holds the key for endpoints like
+/// [crate::models::v3::keys::ApiKeyDetails].
+struct TransportError(APIError<LuarmorError>);
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact_api_key(&self.0.to_string()))
+    }
+}
+impl fmt::Debug for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact_api_key(&format!("{:?}", self.0)))
+    }
+}
+// Skips the wrapped error itself, as it formats with the URL
+impl Error for TransportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.kind().source()
+    }
+}
+
+/// Deserializes `body`, keeping track of where any error occurred.
+pub(crate) fn decode<T: DeserializeOwned>(body: &Bytes) -> Result<T, LuarmorError> {
+    serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(body)).map_err(
+        |err| LuarmorError::Decode {
+            body: body.clone(),
+            path: err.path().to_string(),
+            source: Arc::new(err.into_inner()),
+        },
+    )
+}
+
+/// Deserializes a response which isn't wrapped in a [crate::models::LuarmorResponse].
+pub(crate) fn decode_response<T: DeserializeOwned>(
+    response: Response<Bytes>,
+) -> Result<T, LuarmorError> {
+    if !response.status().is_success() {
+        return Err(LuarmorError::Http {
+            status: response.status(),
+            body: response.into_body(),
+        });
+    }
+
+    decode(response.body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_api_key_from_paths() {
+        assert_eq!(
+            redact_api_key("error sending request for url (http://x/v3/keys/abc-123/details)"),
+            "error sending request for url (http://x/v3/keys/[REDACTED]/details)"
+        );
+        assert_eq!(
+            redact_api_key("/v3/keys/a/stats, /v3/keys/b"),
+            "/v3/keys/[REDACTED]/stats, /v3/keys/[REDACTED]"
+        );
+        assert_eq!(redact_api_key("/v3/projects/a"), "/v3/projects/a");
+    }
+
+    #[test]
+    fn transport_errors_hide_api_key() {
+        // Nothing listens on the discard port, so this fails without leaving the machine
+        let err =
+            reqwest::blocking::get("http://127.0.0.1:9/v3/keys/secretkey/details").unwrap_err();
+        assert!(err.to_string().contains("secretkey"));
+
+        let err = LuarmorError::from(APIError::<LuarmorError>::from(err));
+        assert!(matches!(err, LuarmorError::Transport(_)));
+        assert!(!err.to_string().contains("secretkey"), "{err}");
+        assert!(!format!("{err:?}").contains("secretkey"), "{err:?}");
+
+        let mut source = err.source();
+        while let Some(err) = source {
+            assert!(!err.to_string().contains("secretkey"), "{err}");
+            source = err.source();
+        }
+    }
+}
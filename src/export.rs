@@ -0,0 +1,373 @@
+use std::io::{self, Write};
+
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use serde_json::Value;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use typed_builder::TypedBuilder;
+
+use crate::{
+    LuarmorError,
+    models::v3::projects::users::{User, UserExpiration},
+    secret::REDACTED,
+};
+
+/// A field of [User] which can be exported.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ExportColumn {
+    UserKey,
+    Identifier,
+    IdentifierType,
+    DiscordId,
+    Status,
+    LastReset,
+    TotalResets,
+    AuthExpire,
+    Banned,
+    BanReason,
+    BanExpire,
+    UnbanToken,
+    TotalExecutions,
+    Note,
+    BanIp,
+}
+impl ExportColumn {
+    /// Every column, in the order Luarmor sends them.
+    pub const ALL: [Self; 15] = [
+        Self::UserKey,
+        Self::Identifier,
+        Self::IdentifierType,
+        Self::DiscordId,
+        Self::Status,
+        Self::LastReset,
+        Self::TotalResets,
+        Self::AuthExpire,
+        Self::Banned,
+        Self::BanReason,
+        Self::BanExpire,
+        Self::UnbanToken,
+        Self::TotalExecutions,
+        Self::Note,
+        Self::BanIp,
+    ];
+
+    /// The header, matching the field name Luarmor uses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UserKey => "user_key",
+            Self::Identifier => "identifier",
+            Self::IdentifierType => "identifier_type",
+            Self::DiscordId => "discord_id",
+            Self::Status => "status",
+            Self::LastReset => "last_reset",
+            Self::TotalResets => "total_resets",
+            Self::AuthExpire => "auth_expire",
+            Self::Banned => "banned",
+            Self::BanReason => "ban_reason",
+            Self::BanExpire => "ban_expire",
+            Self::UnbanToken => "unban_token",
+            Self::TotalExecutions => "total_executions",
+            Self::Note => "note",
+            Self::BanIp => "ban_ip",
+        }
+    }
+
+    /// If the column is hidden by [ExportOptions::redact].
+    pub fn is_sensitive(&self) -> bool {
+        matches!(self, Self::UserKey | Self::Identifier | Self::BanIp)
+    }
+
+    fn value(&self, user: &User) -> Value {
+        fn text(x: Option<&str>) -> Value {
+            x.map_or(Value::Null, |x| Value::String(x.to_string()))
+        }
+
+        match self {
+            Self::UserKey => text(user.user_key.as_deref()),
+            Self::Identifier => text(user.identifier.as_deref()),
+            Self::IdentifierType => text(Some(user.identifier_type.as_str())),
+            Self::DiscordId => text(user.discord_id.as_deref()),
+            Self::Status => text(Some(user.status.as_str())),
+            // `0` if the HWID was never reset
+            Self::LastReset => match user.last_reset.unix_timestamp() {
+                0 => Value::String("never".to_string()),
+                _ => timestamp(user.last_reset),
+            },
+            Self::TotalResets => user.total_resets.into(),
+            Self::AuthExpire => match user.auth_expire {
+                UserExpiration::Never => Value::String("never".to_string()),
+                UserExpiration::Specified(x) => timestamp(x),
+            },
+            Self::Banned => user.banned.into(),
+            Self::BanReason => text(user.ban_reason.as_deref()),
+            Self::BanExpire => match user.ban_expire.unix_timestamp() {
+                x if x < 0 => Value::String("never".to_string()),
+                _ => timestamp(user.ban_expire),
+            },
+            Self::UnbanToken => text(user.unban_token.as_deref()),
+            Self::TotalExecutions => user.total_executions.into(),
+            Self::Note => text(user.note.as_deref()),
+            Self::BanIp => text(user.ban_ip.as_deref()),
+        }
+    }
+}
+
+fn timestamp(x: OffsetDateTime) -> Value {
+    x.format(&Rfc3339).map_or(Value::Null, Value::String)
+}
+
+/// The file format users are exported or imported as.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ExportFormat {
+    /// Comma separated values with a header row, per RFC 4180.
+    Csv,
+    /// A JSON object per line.
+    JsonLines,
+}
+
+/// Controls which users and fields are exported.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
+pub struct ExportOptions {
+    #[builder(default = ExportFormat::Csv)]
+    pub format: ExportFormat,
+    /// The columns to export, in order.
+    #[builder(default = ExportColumn::ALL.to_vec())]
+    pub columns: Vec<ExportColumn>,
+    /// If `true`, sensitive columns (see [ExportColumn::is_sensitive]) are replaced with `[REDACTED]`.
+    #[builder(default = false)]
+    pub redact: bool,
+    /// If `true`, CSV cells starting with `=`, `+`, `-`, `@`, a tab or a carriage return are prefixed with `'`,
+    /// so spreadsheets don't run user-controlled values, e.g. notes, as formulas.
+    #[builder(default = true)]
+    pub escape_formulas: bool,
+    /// How many users to fetch per request, must be positive.
+    #[builder(default = 100)]
+    pub page_size: u32,
+}
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Errors returned while exporting users.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    /// The users could not be fetched.
+    #[error(transparent)]
+    Luarmor(#[from] LuarmorError),
+    /// The output could not be written.
+    #[error("failed to write the export: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Writes users to `W` as they arrive.
+///
+/// Used by [crate::LuarmorClient::export_users], but also works with users fetched elsewhere.
+#[derive(Debug)]
+pub struct UserWriter<W> {
+    writer: W,
+    options: ExportOptions,
+    rows: usize,
+}
+impl<W: Write> UserWriter<W> {
+    /// Writes the CSV header straight away, so an export without users still has one.
+    pub fn new(mut writer: W, options: ExportOptions) -> io::Result<Self> {
+        if options.format == ExportFormat::Csv {
+            let header = options.columns.iter().map(|x| x.name().to_string());
+            write_csv_row(&mut writer, header)?;
+        }
+
+        Ok(Self {
+            writer,
+            options,
+            rows: 0,
+        })
+    }
+
+    pub fn write(&mut self, user: &User) -> io::Result<()> {
+        let values = self.options.columns.iter().map(|column| {
+            let value = column.value(user);
+            match value {
+                Value::Null => value,
+                _ if self.options.redact && column.is_sensitive() => REDACTED.into(),
+                _ => value,
+            }
+        });
+
+        match self.options.format {
+            ExportFormat::Csv => write_csv_row(
+                &mut self.writer,
+                values.map(|x| match x {
+                    Value::Null => String::new(),
+                    Value::String(x) if self.options.escape_formulas && is_formula(&x) => {
+                        format!("'{x}")
+                    }
+                    Value::String(x) => x,
+                    x => x.to_string(),
+                }),
+            )?,
+            ExportFormat::JsonLines => {
+                // `serde_json::Map` sorts its keys, so serialize the pairs to keep the column order
+                let names = self.options.columns.iter().map(|x| x.name());
+                let row: Vec<_> = names.zip(values).collect();
+                serde_json::to_writer(&mut self.writer, &JsonRow(&row))?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// The number of users written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Flushes and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// A row of JSON Lines, in column order.
+struct JsonRow<'a>(&'a [(&'static str, Value)]);
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// If a spreadsheet would treat `cell` as a formula.
+pub(crate) fn is_formula(cell: &str) -> bool {
+    cell.starts_with(['=', '+', '-', '@', '\t', '\r'])
+}
+
+fn write_csv_row(writer: &mut impl Write, fields: impl Iterator<Item = String>) -> io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+
+        // Quote anything which would otherwise break the row, doubling any quotes
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::v3::projects::users::test_user;
+
+    #[test]
+    fn unreset_keys_export_as_never() {
+        let user = test_user(json!({ "last_reset": 0 }));
+        assert_eq!(ExportColumn::LastReset.value(&user), json!("never"));
+        let user = test_user(json!({ "last_reset": 1714521600 }));
+        assert_eq!(
+            ExportColumn::LastReset.value(&user),
+            json!("2024-05-01T00:00:00Z")
+        );
+    }
+
+    /// Exports `users` with `options`, as text.
+    fn export(users: &[User], options: ExportOptions) -> String {
+        let mut writer = UserWriter::new(Vec::new(), options).unwrap();
+        for user in users {
+            writer.write(user).unwrap();
+        }
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    fn columns(columns: &[ExportColumn]) -> ExportOptions {
+        ExportOptions::builder().columns(columns.to_vec()).build()
+    }
+
+    #[test]
+    fn csv_cells_are_quoted() {
+        let user = test_user(json!({ "note": "a, \"b\"\nc", "ban_reason": "plain" }));
+        let csv = export(
+            &[user],
+            columns(&[ExportColumn::Note, ExportColumn::BanReason]),
+        );
+        assert_eq!(csv, "note,ban_reason\r\n\"a, \"\"b\"\"\nc\",plain\r\n");
+    }
+
+    #[test]
+    fn csv_formulas_are_escaped() {
+        let users = [test_user(
+            json!({ "note": "=HYPERLINK(\"x\")", "ban_reason": "@SUM(A1)" }),
+        )];
+        let options = columns(&[ExportColumn::Note, ExportColumn::BanReason]);
+        assert_eq!(
+            export(&users, options.clone()),
+            "note,ban_reason\r\n\"'=HYPERLINK(\"\"x\"\")\",'@SUM(A1)\r\n"
+        );
+
+        let options = ExportOptions {
+            escape_formulas: false,
+            ..options
+        };
+        assert_eq!(
+            export(&users, options),
+            "note,ban_reason\r\n\"=HYPERLINK(\"\"x\"\")\",@SUM(A1)\r\n"
+        );
+    }
+
+    #[test]
+    fn sensitive_columns_are_redacted() {
+        let user = test_user(json!({ "identifier": "hwid", "note": "kept" }));
+        let options = ExportOptions {
+            redact: true,
+            ..columns(&[
+                ExportColumn::UserKey,
+                ExportColumn::Identifier,
+                ExportColumn::BanIp,
+                ExportColumn::Note,
+            ])
+        };
+        // Empty values stay empty
+        assert_eq!(
+            export(&[user], options),
+            "user_key,identifier,ban_ip,note\r\n[REDACTED],[REDACTED],,kept\r\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_keep_the_column_order() {
+        let users = [
+            test_user(json!({ "note": "=not a formula" })),
+            test_user(json!({ "user_key": "key2", "banned": 1 })),
+        ];
+        let options = ExportOptions {
+            format: ExportFormat::JsonLines,
+            ..columns(&[
+                ExportColumn::UserKey,
+                ExportColumn::Note,
+                ExportColumn::Banned,
+                ExportColumn::AuthExpire,
+            ])
+        };
+        assert_eq!(
+            export(&users, options),
+            concat!(
+                r#"{"user_key":"key1","note":"=not a formula","banned":false,"auth_expire":"never"}"#,
+                "\n",
+                r#"{"user_key":"key2","note":null,"banned":true,"auth_expire":"never"}"#,
+                "\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,516 @@
+use std::io::Write;
+
+use api_builder::{AsyncClient, Client};
+
+use crate::{
+    BulkReport, Clock, DesiredUser, ExportError, ExportOptions, ImportOptions, ImportReport,
+    LuarmorClient, LuarmorError, ReconcileError, ReconcileOptions, ReconcilePlan, SweepOptions,
+    SweepReport, UsersIter,
+    models::{
+        DiscordId, ProjectId, ScriptId, UnbanToken, UserKey,
+        v3::projects::{
+            scripts::{ScriptUpload, UpdateScript},
+            users::{
+                BlacklistUser, CreateUser, GetUsers, LinkDiscordId, NewUser, ResetHwid, UpdateUser,
+                User, UserChanges, UserFilter,
+            },
+        },
+    },
+};
+
+/// A [LuarmorClient] scoped to a single project.
+///
+/// Created by [LuarmorClient::project].
+#[derive(Debug)]
+pub struct ProjectHandle<'a, C> {
+    client: &'a LuarmorClient<C>,
+    project_id: &'a ProjectId,
+}
+impl<C> Clone for ProjectHandle<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<C> Copy for ProjectHandle<'_, C> {}
+impl<'a, C> ProjectHandle<'a, C> {
+    pub(crate) fn new(client: &'a LuarmorClient<C>, project_id: &'a ProjectId) -> Self {
+        Self { client, project_id }
+    }
+
+    /// The ID of the project.
+    pub fn id(&self) -> &'a ProjectId {
+        self.project_id
+    }
+
+    /// Scopes to a key within the project.
+    pub fn user(&self, user_key: &'a UserKey) -> UserHandle<'a, C> {
+        UserHandle {
+            project: *self,
+            user_key,
+        }
+    }
+
+    /// Scopes to a script within the project.
+    pub fn script(&self, script_id: &'a ScriptId) -> ScriptHandle<'a, C> {
+        ScriptHandle {
+            project: *self,
+            script_id,
+        }
+    }
+}
+impl<'a, C> ProjectHandle<'a, C>
+where
+    C: Client<Error = LuarmorError>,
+{
+    pub fn users(&self, filter: UserFilter<'_>) -> Result<Vec<User>, LuarmorError> {
+        self.client.users(GetUsers::new(self.project_id, filter))
+    }
+
+    /// See [LuarmorClient::users_iter].
+    pub fn users_iter(&self, filter: UserFilter<'a>, page_size: u32) -> UsersIter<'a, C> {
+        self.client
+            .users_iter(GetUsers::new(self.project_id, filter), page_size)
+    }
+
+    pub fn create_user(&self, user: NewUser<'_>) -> Result<UserKey, LuarmorError> {
+        self.client
+            .create_user(CreateUser::new(self.project_id, user))
+    }
+
+    /// See [LuarmorClient::create_users_bulk].
+    pub fn create_users_bulk<'b>(
+        &self,
+        specs: impl IntoIterator<Item = NewUser<'b>, IntoIter: Send>,
+        concurrency: usize,
+    ) -> BulkReport
+    where
+        C: Sync,
+    {
+        self.client
+            .create_users_bulk(self.project_id, specs, concurrency)
+    }
+
+    /// See [LuarmorClient::import_users].
+    pub fn import_users(&self, text: &str, options: ImportOptions) -> ImportReport
+    where
+        C: Sync,
+    {
+        self.client.import_users(self.project_id, text, options)
+    }
+
+    /// See [LuarmorClient::plan_reconcile].
+    pub fn plan_reconcile(
+        &self,
+        desired: impl IntoIterator<Item = DesiredUser>,
+        options: &ReconcileOptions,
+    ) -> Result<ReconcilePlan, ReconcileError> {
+        self.client
+            .plan_reconcile(self.project_id, desired, options)
+    }
+
+    /// See [LuarmorClient::sweep_expired].
+    pub fn sweep_expired(
+        &self,
+        options: &SweepOptions,
+        clock: &impl Clock,
+    ) -> Result<SweepReport, LuarmorError> {
+        self.client.sweep_expired(self.project_id, options, clock)
+    }
+
+    /// See [LuarmorClient::export_users].
+    pub fn export_users(
+        &self,
+        writer: impl Write,
+        options: ExportOptions,
+    ) -> Result<usize, ExportError> {
+        self.client.export_users(self.project_id, writer, options)
+    }
+
+    pub fn unblacklist(&self, unban_token: &UnbanToken) -> Result<(), LuarmorError> {
+        self.client.unblacklist(self.project_id, unban_token)
+    }
+}
+impl<C> ProjectHandle<'_, C>
+where
+    C: AsyncClient<Error = LuarmorError> + Sync,
+{
+    pub async fn users_async(&self, filter: UserFilter<'_>) -> Result<Vec<User>, LuarmorError> {
+        self.client
+            .users_async(GetUsers::new(self.project_id, filter))
+            .await
+    }
+
+    pub async fn create_user_async(&self, user: NewUser<'_>) -> Result<UserKey, LuarmorError> {
+        self.client
+            .create_user_async(CreateUser::new(self.project_id, user))
+            .await
+            .map(|x| x.user_key)
+    }
+
+    /// See [LuarmorClient::create_users_bulk_async].
+    pub async fn create_users_bulk_async<'b>(
+        &self,
+        specs: impl IntoIterator<Item = NewUser<'b>>,
+        concurrency: usize,
+    ) -> BulkReport {
+        self.client
+            .create_users_bulk_async(self.project_id, specs, concurrency)
+            .await
+    }
+
+    /// See [LuarmorClient::import_users_async].
+    pub async fn import_users_async(&self, text: &str, options: ImportOptions) -> ImportReport {
+        self.client
+            .import_users_async(self.project_id, text, options)
+            .await
+    }
+
+    /// See [LuarmorClient::plan_reconcile_async].
+    pub async fn plan_reconcile_async(
+        &self,
+        desired: impl IntoIterator<Item = DesiredUser>,
+        options: &ReconcileOptions,
+    ) -> Result<ReconcilePlan, ReconcileError> {
+        self.client
+            .plan_reconcile_async(self.project_id, desired, options)
+            .await
+    }
+
+    /// See [LuarmorClient::sweep_expired_async].
+    pub async fn sweep_expired_async(
+        &self,
+        options: &SweepOptions,
+        clock: &impl Clock,
+    ) -> Result<SweepReport, LuarmorError> {
+        self.client
+            .sweep_expired_async(self.project_id, options, clock)
+            .await
+    }
+
+    /// See [LuarmorClient::export_users_async].
+    pub async fn export_users_async(
+        &self,
+        writer: impl Write,
+        options: ExportOptions,
+    ) -> Result<usize, ExportError> {
+        self.client
+            .export_users_async(self.project_id, writer, options)
+            .await
+    }
+
+    pub async fn unblacklist_async(&self, unban_token: &UnbanToken) -> Result<(), LuarmorError> {
+        self.client
+            .unblacklist_async(self.project_id, unban_token)
+            .await
+    }
+}
+
+/// A [LuarmorClient] scoped to a single key.
+///
+/// Created by [ProjectHandle::user].
+#[derive(Debug)]
+pub struct UserHandle<'a, C> {
+    project: ProjectHandle<'a, C>,
+    user_key: &'a UserKey,
+}
+impl<C> Clone for UserHandle<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<C> Copy for UserHandle<'_, C> {}
+impl<'a, C> UserHandle<'a, C> {
+    /// The project which contains the key.
+    pub fn project(&self) -> ProjectHandle<'a, C> {
+        self.project
+    }
+
+    /// The key.
+    pub fn key(&self) -> &'a UserKey {
+        self.user_key
+    }
+
+    fn filter(&self) -> GetUsers<'a> {
+        GetUsers::builder()
+            .project_id(self.project.project_id)
+            .user_key(self.user_key)
+            .build()
+    }
+
+    fn reset_hwid_payload(&self, force: bool) -> ResetHwid<'a> {
+        ResetHwid::builder()
+            .project_id(self.project.project_id)
+            .user_key(self.user_key)
+            .force(force)
+            .build()
+    }
+
+    fn link_discord_payload(&self, discord_id: &'a DiscordId, force: bool) -> LinkDiscordId<'a> {
+        LinkDiscordId::builder()
+            .project_id(self.project.project_id)
+            .user_key(self.user_key)
+            .discord_id(discord_id)
+            .force(force)
+            .build()
+    }
+
+    fn blacklist_payload<'b>(
+        &self,
+        ban_reason: Option<&'b str>,
+        ban_expire: Option<i32>,
+    ) -> BlacklistUser<'b>
+    where
+        'a: 'b,
+    {
+        BlacklistUser {
+            project_id: self.project.project_id.into(),
+            user_key: self.user_key.into(),
+            ban_reason: ban_reason.map(Into::into),
+            ban_expire,
+        }
+    }
+}
+impl<C> UserHandle<'_, C>
+where
+    C: Client<Error = LuarmorError>,
+{
+    /// Fetches the key, if it exists.
+    pub fn get(&self) -> Result<Option<User>, LuarmorError> {
+        self.project
+            .client
+            .users(self.filter())
+            .map(|x| x.into_iter().next())
+    }
+
+    /// Resets the HWID, `force` ignores the cooldown.
+    pub fn reset_hwid(&self, force: bool) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .reset_hwid(self.reset_hwid_payload(force))
+    }
+
+    /// Links `discord_id`, `force` overwrites any currently linked Discord ID.
+    pub fn link_discord(&self, discord_id: &DiscordId, force: bool) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .link_discord(self.link_discord_payload(discord_id, force))
+    }
+
+    /// Blacklists the key, see [BlacklistUser] for `ban_reason` and `ban_expire`.
+    pub fn blacklist(
+        &self,
+        ban_reason: Option<&str>,
+        ban_expire: Option<i32>,
+    ) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .blacklist(self.blacklist_payload(ban_reason, ban_expire))
+    }
+
+    pub fn update(&self, changes: UserChanges<'_>) -> Result<(), LuarmorError> {
+        self.project.client.update_user(UpdateUser::new(
+            self.project.project_id,
+            self.user_key,
+            changes,
+        ))
+    }
+
+    pub fn delete(&self) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .delete_user(self.project.project_id, self.user_key)
+    }
+}
+impl<C> UserHandle<'_, C>
+where
+    C: AsyncClient<Error = LuarmorError> + Sync,
+{
+    /// Fetches the key, if it exists.
+    pub async fn get_async(&self) -> Result<Option<User>, LuarmorError> {
+        self.project
+            .client
+            .users_async(self.filter())
+            .await
+            .map(|x| x.into_iter().next())
+    }
+
+    /// Resets the HWID, `force` ignores the cooldown.
+    pub async fn reset_hwid_async(&self, force: bool) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .reset_hwid_async(self.reset_hwid_payload(force))
+            .await
+    }
+
+    /// Links `discord_id`, `force` overwrites any currently linked Discord ID.
+    pub async fn link_discord_async(
+        &self,
+        discord_id: &DiscordId,
+        force: bool,
+    ) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .link_discord_async(self.link_discord_payload(discord_id, force))
+            .await
+    }
+
+    /// Blacklists the key, see [BlacklistUser] for `ban_reason` and `ban_expire`.
+    pub async fn blacklist_async(
+        &self,
+        ban_reason: Option<&str>,
+        ban_expire: Option<i32>,
+    ) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .blacklist_async(self.blacklist_payload(ban_reason, ban_expire))
+            .await
+    }
+
+    pub async fn update_async(&self, changes: UserChanges<'_>) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .update_user_async(UpdateUser::new(
+                self.project.project_id,
+                self.user_key,
+                changes,
+            ))
+            .await
+    }
+
+    pub async fn delete_async(&self) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .delete_user_async(self.project.project_id, self.user_key)
+            .await
+    }
+}
+
+/// A [LuarmorClient] scoped to a single script.
+///
+/// Created by [ProjectHandle::script].
+#[derive(Debug)]
+pub struct ScriptHandle<'a, C> {
+    project: ProjectHandle<'a, C>,
+    script_id: &'a ScriptId,
+}
+impl<C> Clone for ScriptHandle<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<C> Copy for ScriptHandle<'_, C> {}
+impl<'a, C> ScriptHandle<'a, C> {
+    /// The project which contains the script.
+    pub fn project(&self) -> ProjectHandle<'a, C> {
+        self.project
+    }
+
+    /// The ID of the script.
+    pub fn id(&self) -> &'a ScriptId {
+        self.script_id
+    }
+}
+impl<C> ScriptHandle<'_, C>
+where
+    C: Client<Error = LuarmorError>,
+{
+    pub fn update(&self, upload: ScriptUpload<'_>) -> Result<(), LuarmorError> {
+        self.project.client.update_script(UpdateScript::new(
+            self.project.project_id,
+            self.script_id,
+            upload,
+        ))
+    }
+}
+impl<C> ScriptHandle<'_, C>
+where
+    C: AsyncClient<Error = LuarmorError> + Sync,
+{
+    pub async fn update_async(&self, upload: ScriptUpload<'_>) -> Result<(), LuarmorError> {
+        self.project
+            .client
+            .update_script_async(UpdateScript::new(
+                self.project.project_id,
+                self.script_id,
+                upload,
+            ))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeLuarmor, FakeProject, FakeScript, FakeUser};
+
+    fn fake() -> FakeLuarmor {
+        FakeLuarmor::new("secret").with_project(
+            FakeProject::new("project", "Project")
+                .with_script(FakeScript::new("script", "Script"))
+                .with_user(FakeUser::new("existing")),
+        )
+    }
+
+    #[test]
+    fn requests_are_scoped_to_the_handle() {
+        let fake = fake();
+        let client = fake.client();
+        let project_id = "project".parse().unwrap();
+        let project = client.project(&project_id);
+
+        let user_key = project
+            .create_user(NewUser::builder().note("created").build())
+            .unwrap();
+        let users = project
+            .users(UserFilter::builder().search("created").build())
+            .unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].user_key.as_ref(), Some(&user_key));
+        assert_eq!(project.users(UserFilter::default()).unwrap().len(), 2);
+
+        let user = project.user(&user_key);
+        user.update(UserChanges::builder().note("updated").build())
+            .unwrap();
+        user.blacklist(Some("reason"), None).unwrap();
+        let found = user.get().unwrap().unwrap();
+        assert_eq!(found.note.as_deref(), Some("updated"));
+        assert_eq!(found.ban_reason.as_deref(), Some("reason"));
+
+        let script_id = "script".parse().unwrap();
+        project
+            .script(&script_id)
+            .update(ScriptUpload::builder().script("print()").build())
+            .unwrap();
+        assert_eq!(
+            fake.state().project("project").unwrap().scripts[0].source,
+            "print()"
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_are_scoped_to_the_handle_async() {
+        let fake = fake();
+        let client = fake.client();
+        let project_id = "project".parse().unwrap();
+        let project = client.project(&project_id);
+
+        let user_key = project.create_user_async(NewUser::default()).await.unwrap();
+        let user = project.user(&user_key);
+        user.update_async(UserChanges::builder().note("updated").build())
+            .await
+            .unwrap();
+        user.blacklist_async(None, None).await.unwrap();
+
+        let found = user.get_async().await.unwrap().unwrap();
+        assert_eq!(found.note.as_deref(), Some("updated"));
+        assert!(found.banned);
+        assert_eq!(
+            project
+                .users_async(UserFilter::default())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
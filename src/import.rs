@@ -0,0 +1,489 @@
+use std::{iter::Peekable, mem, str::Chars};
+
+use serde_json::{Map, Value};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use typed_builder::TypedBuilder;
+
+use crate::{
+    BulkReport, ExportFormat, LuarmorError,
+    export::is_formula,
+    models::{
+        DiscordId, Hwid, InvalidId, UserKey,
+        v3::projects::users::{ClaimedKey, KeyOwner, LifetimeKey, NewKey, NewUser, UnclaimedKey},
+    },
+    secret::REDACTED,
+};
+
+/// Controls how users are imported.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
+pub struct ImportOptions {
+    #[builder(default = ExportFormat::Csv)]
+    pub format: ExportFormat,
+    /// If `true`, rows are only validated and no keys are created.
+    #[builder(default = false)]
+    pub dry_run: bool,
+    /// How many keys to create at once.
+    #[builder(default = 4)]
+    pub concurrency: usize,
+}
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Why a row could not be imported.
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum ImportRowError {
+    /// The row could not be read, e.g. an unterminated quote or invalid JSON.
+    #[error("malformed row: {0}")]
+    Malformed(String),
+    #[error(transparent)]
+    InvalidId(#[from] InvalidId),
+    /// The value was hidden by [crate::ExportOptions::redact], so the original is lost.
+    #[error("`{0}` is redacted, export without redacting to import it")]
+    Redacted(&'static str),
+    #[error("invalid key_days `{0}`, expected a positive whole number")]
+    InvalidKeyDays(String),
+    #[error("invalid auth_expire `{0}`, expected an RFC 3339 or unix timestamp, or `never`")]
+    InvalidAuthExpire(String),
+    #[error("only one of auth_expire and key_days can be set")]
+    ConflictingExpiry,
+    /// Unclaimed keys are assigned to whoever redeems them first.
+    #[error("key_days cannot be used with an identifier or discord_id, use auth_expire instead")]
+    UnclaimedWithOwner,
+    /// See [crate::models::v3::projects::users::CreateUser] on `auth_expire` vs `key_days`.
+    #[error("auth_expire requires an identifier or discord_id, use key_days instead")]
+    ClaimedWithoutOwner,
+    /// The row was valid, but Luarmor failed to create the key.
+    #[error("failed to create the key: {0}")]
+    Luarmor(#[from] LuarmorError),
+}
+
+/// A key created from a row.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ImportCreated {
+    /// The line the row starts on, counting from 1.
+    pub line: usize,
+    pub user_key: UserKey,
+}
+
+/// A row which could not be imported.
+#[derive(Clone, Debug)]
+pub struct ImportFailure {
+    /// The line the row starts on, counting from 1.
+    pub line: usize,
+    pub error: ImportRowError,
+}
+
+/// The outcome of [crate::LuarmorClient::import_users], ordered by line.
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    /// The lines of every row which passed validation.
+    pub valid: Vec<usize>,
+    /// Always empty for a dry run.
+    pub created: Vec<ImportCreated>,
+    pub failed: Vec<ImportFailure>,
+}
+impl ImportReport {
+    /// If every row was imported, or would have been for a dry run.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The validated rows of an import, waiting to be created.
+#[derive(Debug)]
+pub(crate) struct Import {
+    report: ImportReport,
+    specs: Vec<NewUser<'static>>,
+}
+impl Import {
+    pub(crate) fn parse(text: &str, format: ExportFormat) -> Self {
+        let rows = match format {
+            ExportFormat::Csv => csv_rows(text),
+            ExportFormat::JsonLines => json_rows(text),
+        };
+
+        let mut import = Self {
+            report: ImportReport::default(),
+            specs: Vec::new(),
+        };
+        for (line, row) in rows {
+            match row.and_then(|x| x.validate()) {
+                Ok(spec) => {
+                    import.report.valid.push(line);
+                    import.specs.push(spec);
+                }
+                Err(error) => import.report.failed.push(ImportFailure { line, error }),
+            }
+        }
+        import
+    }
+
+    /// The specs to create, in the same order as [ImportReport::valid].
+    pub(crate) fn take_specs(&mut self) -> Vec<NewUser<'static>> {
+        mem::take(&mut self.specs)
+    }
+
+    /// Maps the outcome of creating [Import::take_specs] back to lines.
+    pub(crate) fn finish(mut self, bulk: Option<BulkReport>) -> ImportReport {
+        if let Some(bulk) = bulk {
+            let report = &mut self.report;
+            report
+                .created
+                .extend(bulk.created.into_iter().map(|x| ImportCreated {
+                    line: report.valid[x.index],
+                    user_key: x.user_key,
+                }));
+            report
+                .failed
+                .extend(bulk.failed.into_iter().map(|x| ImportFailure {
+                    line: report.valid[x.index],
+                    error: x.error.into(),
+                }));
+            report.failed.sort_by_key(|x| x.line);
+        }
+        self.report
+    }
+}
+
+/// A row before validation, empty values are `None`.
+#[derive(Debug, Default)]
+struct ImportRow {
+    identifier: Option<String>,
+    discord_id: Option<String>,
+    note: Option<String>,
+    auth_expire: Option<String>,
+    key_days: Option<String>,
+}
+impl ImportRow {
+    /// Unknown columns are ignored, so an export can be imported as is.
+    fn set(&mut self, column: &str, value: String) {
+        let value = Some(value).filter(|x| !x.is_empty());
+        match column {
+            "identifier" => self.identifier = value,
+            "discord_id" => self.discord_id = value,
+            "note" => self.note = value,
+            "auth_expire" => self.auth_expire = value,
+            "key_days" => self.key_days = value,
+            _ => {}
+        }
+    }
+
+    /// Checks the row against the rules of [crate::models::v3::projects::users::CreateUser].
+    fn validate(self) -> Result<NewUser<'static>, ImportRowError> {
+        if self.identifier.as_deref() == Some(REDACTED) {
+            return Err(ImportRowError::Redacted("identifier"));
+        }
+        let identifier = self.identifier.map(Hwid::new).transpose()?;
+        let discord_id = self.discord_id.map(DiscordId::new).transpose()?;
+        let owner = match (identifier, discord_id) {
+            (Some(identifier), Some(discord_id)) => Some(KeyOwner::Both {
+                identifier: identifier.into(),
+                discord_id: discord_id.into(),
+            }),
+            (Some(identifier), None) => Some(KeyOwner::Identifier(identifier.into())),
+            (None, Some(discord_id)) => Some(KeyOwner::DiscordId(discord_id.into())),
+            (None, None) => None,
+        };
+
+        let auth_expire = self
+            .auth_expire
+            .map(parse_auth_expire)
+            .transpose()?
+            .flatten();
+        let key_days = self
+            .key_days
+            .map(|x| match x.trim().parse() {
+                Ok(days) if days > 0 => Ok(days),
+                _ => Err(ImportRowError::InvalidKeyDays(x)),
+            })
+            .transpose()?;
+
+        let key: NewKey = match (owner, auth_expire, key_days) {
+            (_, Some(_), Some(_)) => return Err(ImportRowError::ConflictingExpiry),
+            (Some(_), None, Some(_)) => return Err(ImportRowError::UnclaimedWithOwner),
+            (None, Some(_), None) => return Err(ImportRowError::ClaimedWithoutOwner),
+            (None, None, Some(key_days)) => UnclaimedKey { key_days }.into(),
+            (Some(owner), Some(auth_expire), None) => ClaimedKey { owner, auth_expire }.into(),
+            (owner, None, None) => LifetimeKey { owner }.into(),
+        };
+
+        Ok(NewUser {
+            key,
+            note: self.note.map(Into::into),
+        })
+    }
+}
+
+/// `None` if the key never expires, matching what [crate::UserWriter] exports.
+fn parse_auth_expire(value: String) -> Result<Option<OffsetDateTime>, ImportRowError> {
+    let trimmed = value.trim();
+    if trimmed.eq_ignore_ascii_case("never") {
+        return Ok(None);
+    }
+
+    match trimmed.parse::<i64>() {
+        Ok(x) if x < 0 => Some(None),
+        Ok(x) => OffsetDateTime::from_unix_timestamp(x).map(Some).ok(),
+        Err(_) => OffsetDateTime::parse(trimmed, &Rfc3339).map(Some).ok(),
+    }
+    .ok_or(ImportRowError::InvalidAuthExpire(value))
+}
+
+type Rows = Vec<(usize, Result<ImportRow, ImportRowError>)>;
+
+/// Reads CSV with a header row, per RFC 4180.
+fn csv_rows(text: &str) -> Rows {
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    let mut header: Option<Vec<String>> = None;
+    let mut rows = Vec::new();
+    while chars.peek().is_some() {
+        let start = line;
+        let record = csv_record(&mut chars, &mut line);
+        // Blank lines, and rows without any values, e.g. `,,,,`
+        if matches!(&record, Ok(fields) if fields.iter().all(|x| x.trim().is_empty())) {
+            continue;
+        }
+
+        let Some(header) = &header else {
+            match record {
+                Ok(fields) => header = Some(fields.iter().map(|x| x.trim().to_string()).collect()),
+                Err(error) => rows.push((start, Err(error))),
+            }
+            continue;
+        };
+
+        let row = record.and_then(|fields| {
+            if fields.len() != header.len() {
+                return Err(ImportRowError::Malformed(format!(
+                    "expected {} fields, found {}",
+                    header.len(),
+                    fields.len()
+                )));
+            }
+
+            let mut row = ImportRow::default();
+            for (column, value) in header.iter().zip(fields) {
+                // Undo [crate::ExportOptions::escape_formulas]
+                let value = match value.strip_prefix('\'') {
+                    Some(x) if is_formula(x) => x.to_string(),
+                    _ => value,
+                };
+                row.set(column, value);
+            }
+            Ok(row)
+        });
+        rows.push((start, row));
+    }
+    rows
+}
+
+/// Reads a single record, leaving `chars` at the start of the next one.
+fn csv_record(
+    chars: &mut Peekable<Chars<'_>>,
+    line: &mut usize,
+) -> Result<Vec<String>, ImportRowError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    loop {
+        let Some(c) = chars.next() else {
+            if quoted {
+                return Err(ImportRowError::Malformed("unterminated quote".to_string()));
+            }
+            break;
+        };
+
+        match c {
+            '"' if quoted => match chars.peek() {
+                Some('"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                _ => quoted = false,
+            },
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                *line += 1;
+                break;
+            }
+            c => {
+                if c == '\n' {
+                    *line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Reads a JSON object per line, values may be strings, numbers or `null`.
+fn json_rows(text: &str) -> Rows {
+    text.lines()
+        .enumerate()
+        .filter(|(_, x)| !x.trim().is_empty())
+        .filter_map(|(i, x)| Some((i + 1, json_row(x).transpose()?)))
+        .collect()
+}
+
+/// `None` if the object has no values, e.g. `{}`.
+fn json_row(text: &str) -> Result<Option<ImportRow>, ImportRowError> {
+    let object: Map<String, Value> =
+        serde_json::from_str(text).map_err(|err| ImportRowError::Malformed(err.to_string()))?;
+
+    let mut row = ImportRow::default();
+    let mut empty = true;
+    for (column, value) in object {
+        let value = match value {
+            Value::Null => continue,
+            Value::String(x) => x,
+            Value::Number(x) => x.to_string(),
+            _ => {
+                return Err(ImportRowError::Malformed(format!(
+                    "`{column}` must be a string, number or null"
+                )));
+            }
+        };
+        empty &= value.trim().is_empty();
+        row.set(&column, value);
+    }
+    Ok((!empty).then_some(row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeLuarmor, FakeProject, FakeUser};
+
+    fn failures(import: &Import) -> Vec<(usize, String)> {
+        let failed = &import.report.failed;
+        failed
+            .iter()
+            .map(|x| (x.line, x.error.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn skips_rows_without_values() {
+        let csv =
+            "identifier,discord_id,note,auth_expire,key_days\r\n,,,,\r\n\r\n,,,,7\r\n , ,,,\r\n";
+        let import = Import::parse(csv, ExportFormat::Csv);
+        assert_eq!(import.report.valid, [4]);
+        assert!(import.report.failed.is_empty());
+
+        let json = "{}\n{\"note\":null,\"key_days\":\"\"}\n{\"key_days\":7}\n";
+        let import = Import::parse(json, ExportFormat::JsonLines);
+        assert_eq!(import.report.valid, [3]);
+        assert!(import.report.failed.is_empty());
+    }
+
+    #[test]
+    fn rejects_redacted_identifiers() {
+        let csv =
+            "user_key,identifier,auth_expire\n[REDACTED],[REDACTED],never\n[REDACTED],hwid,never\n";
+        let import = Import::parse(csv, ExportFormat::Csv);
+        assert_eq!(import.report.valid, [3]);
+        assert_eq!(
+            failures(&import),
+            [(
+                2,
+                "`identifier` is redacted, export without redacting to import it".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn unescapes_csv_formulas() {
+        let csv = "note,key_days\n'=1+1,1\n'plain,1\n";
+        let mut import = Import::parse(csv, ExportFormat::Csv);
+        let notes: Vec<_> = import.take_specs().into_iter().map(|x| x.note).collect();
+        assert_eq!(notes, [Some("=1+1".into()), Some("'plain".into())]);
+    }
+
+    #[test]
+    fn checks_expiry_rules() {
+        let csv = concat!(
+            "identifier,discord_id,auth_expire,key_days\n",
+            "hwid,,never,\n",
+            ",,,7\n",
+            "hwid,,2030-01-01T00:00:00Z,\n",
+            ",111,1900000000,7\n",
+            "hwid,,,7\n",
+            ",,1900000000,\n",
+            ",,soon,\n",
+            ",,,-1\n",
+        );
+        let import = Import::parse(csv, ExportFormat::Csv);
+        assert_eq!(import.report.valid, [2, 3, 4]);
+        assert_eq!(
+            failures(&import),
+            [
+                (5, ImportRowError::ConflictingExpiry.to_string()),
+                (6, ImportRowError::UnclaimedWithOwner.to_string()),
+                (7, ImportRowError::ClaimedWithoutOwner.to_string()),
+                (
+                    8,
+                    ImportRowError::InvalidAuthExpire("soon".into()).to_string()
+                ),
+                (9, ImportRowError::InvalidKeyDays("-1".into()).to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_quoted_csv_fields() {
+        let csv = "note,key_days\n\"a, \"\"quoted\"\"\nnote\",1\nplain,1\n\"open,1\n";
+        let mut import = Import::parse(csv, ExportFormat::Csv);
+        // The quoted newline moves the next row down a line
+        assert_eq!(import.report.valid, [2, 4]);
+        assert_eq!(
+            failures(&import),
+            [(5, "malformed row: unterminated quote".to_string())]
+        );
+
+        let notes: Vec<_> = import.take_specs().into_iter().map(|x| x.note).collect();
+        assert_eq!(
+            notes,
+            [Some("a, \"quoted\"\nnote".into()), Some("plain".into())]
+        );
+    }
+
+    #[test]
+    fn maps_created_keys_to_lines() {
+        let fake = FakeLuarmor::new("secret").with_project(
+            FakeProject::new("project", "Project")
+                .with_user(FakeUser::new("existing").with_identifier("taken")),
+        );
+        let client = fake.client();
+        let project_id = "project".parse().unwrap();
+        let csv = "identifier,note,key_days\n,first,1\ntaken,,\n,,0\nfree,last,\n";
+        let report = client
+            .project(&project_id)
+            .import_users(csv, ImportOptions::default());
+
+        assert_eq!(report.valid, [2, 3, 5]);
+        let failed: Vec<_> = report.failed.iter().map(|x| x.line).collect();
+        assert_eq!(failed, [3, 4]);
+        assert!(matches!(report.failed[0].error, ImportRowError::Luarmor(_)));
+
+        let state = fake.state();
+        let users = &state.project("project").unwrap().users;
+        let note = |line: usize| {
+            let created = report.created.iter().find(|x| x.line == line).unwrap();
+            let user = users
+                .iter()
+                .find(|x| x.user_key == created.user_key.as_str());
+            user.unwrap().note.clone()
+        };
+        assert_eq!(report.created.len(), 2);
+        assert_eq!((note(2).as_str(), note(5).as_str()), ("first", "last"));
+    }
+}
@@ -8,6 +8,15 @@ macro_rules! import {
     };
 }
 
+mod delay;
 pub mod models;
+mod util;
 
-import!(client, combinator);
+import!(
+    audit, bulk, client, combinator, dry_run, error, export, handle, import, limiter, pagination,
+    reconcile, retry, route, secret, sweep,
+);
+#[cfg(feature = "reqwest")]
+import!(backend);
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -0,0 +1,287 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use api_builder::{AsyncClient, Bytes, Client, RestClient, Url, error::APIError};
+use http::{Request, Response};
+
+use crate::{delay, route::Route};
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token bucket limiting how fast requests are sent.
+///
+/// Every clone shares the same bucket, so one limiter can be attached to many clients via [RateLimited].
+/// Each request costs `1` token unless a weight was set for its [Route] via [RateLimiter::with_weight].
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    requests_per_second: f64,
+    burst: f64,
+    weights: HashMap<Route, u32>,
+}
+impl RateLimiter {
+    /// Creates an instance which refills `requests_per_second` tokens a second, holding up to `burst` tokens.
+    ///
+    /// A `burst` of `0` is treated as `1`, as the bucket could otherwise never send a request.
+    ///
+    /// # Panics
+    ///
+    /// If `requests_per_second` is not positive.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        assert!(
+            requests_per_second > 0.0,
+            "requests_per_second must be positive"
+        );
+
+        let burst = f64::from(burst.max(1));
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            })),
+            requests_per_second,
+            burst,
+            weights: HashMap::new(),
+        }
+    }
+
+    /// Sets how many tokens a request to `route` costs.
+    ///
+    /// e.g. `Route::GetUsers { filtered: false }` can be made more expensive than [Route::Status].
+    pub fn with_weight(mut self, route: Route, weight: u32) -> Self {
+        self.weights.insert(route, weight);
+        self
+    }
+
+    /// How many tokens `request` costs.
+    pub fn weight<B>(&self, request: &Request<B>) -> u32 {
+        Route::from_request(request)
+            .and_then(|x| self.weights.get(&x).copied())
+            .unwrap_or(1)
+    }
+
+    /// Takes the tokens for `request`, returning how long to wait before sending it.
+    pub(crate) fn reserve<B>(&self, request: &Request<B>) -> Duration {
+        let weight = f64::from(self.weight(request));
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let now = Instant::now();
+        let refilled = now.duration_since(bucket.updated).as_secs_f64() * self.requests_per_second;
+        bucket.tokens = (bucket.tokens + refilled).min(self.burst) - weight;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.requests_per_second)
+        }
+    }
+}
+
+/// Wraps a client, waiting on a [RateLimiter] before sending each request.
+///
+/// Place it below [crate::Retry] so every attempt is charged, e.g. `LuarmorClient<Retry<RateLimited<C>>>`.
+/// Every clone shares the same limiter, across the blocking and async paths.
+#[derive(Clone, Debug)]
+pub struct RateLimited<C> {
+    client: C,
+    limiter: RateLimiter,
+}
+impl<C> RateLimited<C> {
+    /// Creates an instance.
+    pub fn new(client: C, limiter: RateLimiter) -> Self {
+        Self { client, limiter }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// The limiter requests wait on.
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+}
+impl<C> RestClient for RateLimited<C>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        self.client.rest_endpoint(path)
+    }
+}
+impl<C> Client for RateLimited<C>
+where
+    C: Client,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let delay = self.limiter.reserve(&request);
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        self.client.rest(request)
+    }
+}
+impl<C> AsyncClient for RateLimited<C>
+where
+    C: AsyncClient + Sync,
+    C::Error: Send,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let delay = self.limiter.reserve(&request);
+        if !delay.is_zero() {
+            delay::sleep(delay).await;
+        }
+        self.client.rest_async(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use http::StatusCode;
+
+    use super::*;
+    use crate::{LuarmorError, Retry, RetryPolicy};
+
+    /// Answers every request with `503`, counting them.
+    #[derive(Clone, Default)]
+    struct Unavailable(Arc<AtomicU32>);
+    impl Unavailable {
+        fn respond(&self) -> Response<Bytes> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let mut response = Response::new(Bytes::new());
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            response
+        }
+    }
+    impl RestClient for Unavailable {
+        type Error = LuarmorError;
+
+        fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+            Ok(Url::parse("https://api.luarmor.net")?.join(path)?)
+        }
+    }
+    impl Client for Unavailable {
+        fn rest(&self, _: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond())
+        }
+    }
+    impl AsyncClient for Unavailable {
+        async fn rest_async(
+            &self,
+            _: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond())
+        }
+    }
+
+    fn status_request() -> Request<Vec<u8>> {
+        Request::get("https://api.luarmor.net/status")
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    /// A client retrying twice, which can afford exactly those three attempts.
+    fn retrying() -> (Retry<RateLimited<Unavailable>>, RateLimiter, Unavailable) {
+        let inner = Unavailable::default();
+        let limiter = RateLimiter::new(0.001, 3);
+        let policy = RetryPolicy::builder()
+            .max_retries(2)
+            .base_delay(Duration::ZERO)
+            .build();
+        let client = Retry::new(RateLimited::new(inner.clone(), limiter.clone()), policy);
+        (client, limiter, inner)
+    }
+
+    fn users_request() -> Request<Vec<u8>> {
+        Request::get("https://api.luarmor.net/v3/projects/project/users")
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    #[test]
+    fn routes_are_weighted() {
+        let limiter = RateLimiter::new(1.0, 5).with_weight(Route::GetUsers { filtered: false }, 5);
+        assert_eq!(limiter.weight(&users_request()), 5);
+        assert_eq!(limiter.weight(&status_request()), 1);
+
+        // The unfiltered listing takes the whole burst, so the next request waits for a token
+        assert!(limiter.reserve(&users_request()).is_zero());
+        let delay = limiter.reserve(&status_request());
+        assert!(delay > Duration::from_millis(900), "waited {delay:?}");
+    }
+
+    #[test]
+    fn burst_zero_allows_one_request() {
+        let limiter = RateLimiter::new(1.0, 0);
+        assert!(limiter.reserve(&status_request()).is_zero());
+        assert!(!limiter.reserve(&status_request()).is_zero());
+    }
+
+    #[test]
+    #[should_panic = "requests_per_second must be positive"]
+    fn zero_rate_panics() {
+        RateLimiter::new(0.0, 1);
+    }
+
+    #[test]
+    fn requests_wait_for_tokens() {
+        let client = RateLimited::new(Unavailable::default(), RateLimiter::new(20.0, 1));
+        let start = Instant::now();
+        for _ in 0..3 {
+            client.rest(status_request()).unwrap();
+        }
+
+        // The first request is free, the other two wait 50ms each
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(90), "took {elapsed:?}");
+        assert_eq!(client.inner().0.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn requests_wait_for_tokens_async() {
+        let client = RateLimited::new(Unavailable::default(), RateLimiter::new(20.0, 1));
+        let start = Instant::now();
+        for _ in 0..3 {
+            client.rest_async(status_request()).await.unwrap();
+        }
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(90), "took {elapsed:?}");
+        assert_eq!(client.inner().0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn every_retry_is_charged() {
+        let (client, limiter, inner) = retrying();
+        client.rest(status_request()).unwrap();
+
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+        assert!(!limiter.reserve(&status_request()).is_zero());
+    }
+
+    #[tokio::test]
+    async fn every_retry_is_charged_async() {
+        let (client, limiter, inner) = retrying();
+        client.rest_async(status_request()).await.unwrap();
+
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+        assert!(!limiter.reserve(&status_request()).is_zero());
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    borrow::{Borrow, Cow},
+    fmt,
+    ops::Deref,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::models::Clearable;
+
+/// Why a string was rejected as an ID.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum InvalidIdReason {
+    #[error("cannot be empty")]
+    Empty,
+    #[error("must only contain letters and digits")]
+    NotAlphanumeric,
+    #[error("must only contain digits")]
+    NotNumeric,
+    #[error("must be {expected} characters long, found {found}")]
+    Length { expected: usize, found: usize },
+}
+
+/// A string which is not a valid ID of the given kind.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+#[error("invalid {kind}: {reason}")]
+pub struct InvalidId {
+    /// The name of the ID type, e.g. `ProjectId`.
+    pub kind: &'static str,
+    pub reason: InvalidIdReason,
+}
+
+fn alphanumeric(x: &str) -> Result<(), InvalidIdReason> {
+    match x {
+        "" => Err(InvalidIdReason::Empty),
+        x if !x.chars().all(|c| c.is_ascii_alphanumeric()) => Err(InvalidIdReason::NotAlphanumeric),
+        _ => Ok(()),
+    }
+}
+
+fn numeric(x: &str) -> Result<(), InvalidIdReason> {
+    match x {
+        "" => Err(InvalidIdReason::Empty),
+        x if !x.chars().all(|c| c.is_ascii_digit()) => Err(InvalidIdReason::NotNumeric),
+        _ => Ok(()),
+    }
+}
+
+fn non_empty(x: &str) -> Result<(), InvalidIdReason> {
+    match x {
+        "" => Err(InvalidIdReason::Empty),
+        _ => Ok(()),
+    }
+}
+
+fn unban_token(x: &str) -> Result<(), InvalidIdReason> {
+    alphanumeric(x)?;
+    match x.len() {
+        32 => Ok(()),
+        found => Err(InvalidIdReason::Length {
+            expected: 32,
+            found,
+        }),
+    }
+}
+
+macro_rules! id {
+    ($(#[$meta:meta])* $name:ident, $validate:path) => {
+        $(#[$meta])*
+        #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+        pub struct $name(String);
+        impl $name {
+            /// Validates `value`, unlike deserializing, which accepts whatever Luarmor sends.
+            pub fn new(value: impl Into<String>) -> Result<Self, InvalidId> {
+                let value = value.into();
+                $validate(&value).map_err(|reason| InvalidId {
+                    kind: stringify!($name),
+                    reason,
+                })?;
+                Ok(Self(value))
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+        impl<'a> From<&'a $name> for Cow<'a, $name> {
+            fn from(value: &'a $name) -> Self {
+                Cow::Borrowed(value)
+            }
+        }
+        impl From<$name> for Cow<'_, $name> {
+            fn from(value: $name) -> Self {
+                Cow::Owned(value)
+            }
+        }
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+        impl FromStr for $name {
+            type Err = InvalidId;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::new(s)
+            }
+        }
+        impl TryFrom<String> for $name {
+            type Error = InvalidId;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                Self::new(value)
+            }
+        }
+        impl TryFrom<&str> for $name {
+            type Error = InvalidId;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                Self::new(value)
+            }
+        }
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+        // Values from Luarmor aren't validated, so a new format doesn't break decoding whole responses
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer).map(Self)
+            }
+        }
+        impl Clearable for $name {
+            fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+
+            fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str("")
+            }
+        }
+    };
+}
+
+id!(
+    /// The ID of a project, made of letters and digits.
+    ProjectId,
+    alphanumeric
+);
+id!(
+    /// The ID of a script, made of letters and digits.
+    ScriptId,
+    alphanumeric
+);
+id!(
+    /// A key, made of letters and digits.
+    UserKey,
+    alphanumeric
+);
+id!(
+    /// The token to unblacklist a key, 32 letters and digits.
+    UnbanToken,
+    unban_token
+);
+id!(
+    /// A Discord user ID (snowflake), made of digits.
+    DiscordId,
+    numeric
+);
+id!(
+    /// The identifier of a user, usually their HWID.
+    Hwid,
+    non_empty
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason<T>(result: Result<T, InvalidId>) -> Option<InvalidIdReason> {
+        result.err().map(|x| x.reason)
+    }
+
+    #[test]
+    fn alphanumeric_ids() {
+        assert_eq!(ProjectId::new("abc123").unwrap(), "abc123");
+        assert_eq!(ScriptId::new("S1").unwrap(), "S1");
+        assert_eq!(UserKey::new("GhVDxfYKzrJn").unwrap(), "GhVDxfYKzrJn");
+
+        assert_eq!(reason(ProjectId::new("")), Some(InvalidIdReason::Empty));
+        assert_eq!(
+            reason(ScriptId::new("a/b")),
+            Some(InvalidIdReason::NotAlphanumeric)
+        );
+        assert_eq!(
+            reason(UserKey::new("key 1")),
+            Some(InvalidIdReason::NotAlphanumeric)
+        );
+        assert_eq!(
+            UserKey::new("é").unwrap_err().to_string(),
+            "invalid UserKey: must only contain letters and digits"
+        );
+    }
+
+    #[test]
+    fn unban_tokens() {
+        assert!(UnbanToken::new("a".repeat(32)).is_ok());
+        assert_eq!(reason(UnbanToken::new("")), Some(InvalidIdReason::Empty));
+        assert_eq!(
+            reason(UnbanToken::new("a".repeat(31))),
+            Some(InvalidIdReason::Length {
+                expected: 32,
+                found: 31
+            })
+        );
+        assert_eq!(
+            reason(UnbanToken::new("-".repeat(32))),
+            Some(InvalidIdReason::NotAlphanumeric)
+        );
+    }
+
+    #[test]
+    fn discord_ids() {
+        assert!(DiscordId::new("1030578127133286410").is_ok());
+        assert_eq!(reason(DiscordId::new("")), Some(InvalidIdReason::Empty));
+        assert_eq!(
+            reason(DiscordId::new("12a")),
+            Some(InvalidIdReason::NotNumeric)
+        );
+    }
+
+    #[test]
+    fn hwids() {
+        assert!(Hwid::new("5ad3-f2b4 c1e0").is_ok());
+        assert_eq!(reason(Hwid::new("")), Some(InvalidIdReason::Empty));
+    }
+
+    #[test]
+    fn deserializing_accepts_any_value() {
+        let key: UserKey = serde_json::from_str(r#""key-1""#).unwrap();
+        assert_eq!(key, "key-1");
+        let token: UnbanToken = serde_json::from_str(r#""short""#).unwrap();
+        assert_eq!(token, "short");
+        assert!("key-1".parse::<UserKey>().is_err());
+    }
+}
@@ -1,4 +1,11 @@
+use std::borrow::Cow;
+
 pub mod status;
 pub mod v3;
 
-import!(response);
+import!(ids, patch, response);
+
+/// Clones a borrowed value, so it no longer borrows anything.
+pub(crate) fn owned<T: ToOwned + ?Sized + 'static>(value: Cow<'_, T>) -> Cow<'static, T> {
+    Cow::Owned(value.into_owned())
+}
@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+
+use serde::{Serialize, Serializer};
+use time::OffsetDateTime;
+
+/// A change to a single field of an existing resource.
+///
+/// Fields which are [Patch::Unchanged] should be skipped when serializing, e.g. with
+/// `#[serde(skip_serializing_if = "Patch::is_unchanged")]`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Patch<T> {
+    /// Leave the field as it is.
+    #[default]
+    Unchanged,
+    /// Set the field to a value.
+    Set(T),
+    /// Reset the field, e.g. an empty string or a key that never expires.
+    Clear,
+}
+impl<T> Patch<T> {
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Self::Unchanged)
+    }
+
+    pub fn as_ref(&self) -> Patch<&T> {
+        match self {
+            Self::Unchanged => Patch::Unchanged,
+            Self::Set(x) => Patch::Set(x),
+            Self::Clear => Patch::Clear,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Self::Unchanged => Patch::Unchanged,
+            Self::Set(x) => Patch::Set(f(x)),
+            Self::Clear => Patch::Clear,
+        }
+    }
+}
+impl<T> From<T> for Patch<T> {
+    fn from(value: T) -> Self {
+        Self::Set(value)
+    }
+}
+impl<'a, T: ToOwned + ?Sized> From<&'a T> for Patch<Cow<'a, T>> {
+    fn from(value: &'a T) -> Self {
+        Self::Set(Cow::Borrowed(value))
+    }
+}
+impl<T: Clearable> Serialize for Patch<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unchanged => serializer.serialize_none(),
+            Self::Set(x) => x.serialize_set(serializer),
+            Self::Clear => T::serialize_clear(serializer),
+        }
+    }
+}
+
+/// A value which the API knows how to clear, used to serialize [Patch].
+pub trait Clearable {
+    fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error>;
+}
+impl Clearable for str {
+    fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+
+    /// The API clears strings when they are empty.
+    fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("")
+    }
+}
+impl<T: Clearable + ?Sized> Clearable for &T {
+    fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize_set(serializer)
+    }
+
+    fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+        T::serialize_clear(serializer)
+    }
+}
+impl<T: Clearable + ToOwned + ?Sized> Clearable for Cow<'_, T> {
+    fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize_set(serializer)
+    }
+
+    fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+        T::serialize_clear(serializer)
+    }
+}
+impl Clearable for String {
+    fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+
+    fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("")
+    }
+}
+impl Clearable for OffsetDateTime {
+    fn serialize_set<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.unix_timestamp())
+    }
+
+    /// The API treats `-1` as never.
+    fn serialize_clear<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(-1)
+    }
+}
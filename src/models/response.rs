@@ -1,7 +1,7 @@
 use std::{convert::Infallible, str::FromStr};
 
 use api_builder::APIClientError;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 
 /// Possible messages.
 ///
@@ -85,6 +85,76 @@ pub enum LuarmorMessage {
     Other(String),
 }
 impl APIClientError for LuarmorMessage {}
+impl LuarmorMessage {
+    /// The message exactly as Luarmor sends it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::APIWorking => "API is up and working!",
+            Self::IncorrectAPIKey => "Invalid API key! Visit https://luarmor.net/ to get access.",
+            Self::InvalidAPIKey => "Wrong API key",
+            Self::Success => "Success!",
+            Self::UserDeleted => "User has been deleted!",
+            Self::KeyNotFound => "Key not found",
+            Self::ProjectNotFound => "Project not found!",
+            Self::SuccessReset => "Successfully reset!",
+            Self::UserCooldown => "User is on cooldown.",
+            Self::UserKeyNotFound => "User key doesn't exist",
+            Self::DiscordAlreadyLinked => "This key already has a discord linked to it",
+            Self::NothingToSee => "nothing to see here.",
+            Self::IdentifierAlreadyExists => "Identifier already exists.",
+            Self::InvalidDiscordId => "Invalid discord_id",
+            Self::DiscordIdSuccess => "Discord ID successfully linked!",
+            Self::ResetHWIDDisabled => "Reset Hwid is disabled for this script",
+            Self::EditSuccess => "User has been edited successfully!",
+            Self::DiscordAlreadyExists => "Discord ID already exists",
+            Self::Other(x) => x.as_str(),
+        }
+    }
+
+    /// The category of the message.
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Self::APIWorking
+            | Self::Success
+            | Self::UserDeleted
+            | Self::SuccessReset
+            | Self::DiscordIdSuccess
+            | Self::EditSuccess => MessageKind::Success,
+            Self::KeyNotFound | Self::ProjectNotFound | Self::UserKeyNotFound => {
+                MessageKind::NotFound
+            }
+            Self::DiscordAlreadyLinked
+            | Self::IdentifierAlreadyExists
+            | Self::DiscordAlreadyExists => MessageKind::Conflict,
+            Self::UserCooldown => MessageKind::Cooldown,
+            Self::IncorrectAPIKey | Self::InvalidAPIKey => MessageKind::AuthFailure,
+            Self::ResetHWIDDisabled => MessageKind::Disabled,
+            Self::InvalidDiscordId => MessageKind::Invalid,
+            Self::NothingToSee | Self::Other(_) => MessageKind::Unknown,
+        }
+    }
+
+    /// If the request succeeded.
+    pub fn is_success(&self) -> bool {
+        self.kind() == MessageKind::Success
+    }
+
+    /// If the key, user or project does not exist.
+    pub fn is_not_found(&self) -> bool {
+        self.kind() == MessageKind::NotFound
+    }
+
+    /// If the request clashes with an existing key, identifier or Discord ID.
+    pub fn is_conflict(&self) -> bool {
+        self.kind() == MessageKind::Conflict
+    }
+
+    /// If the same request may succeed later on, without any changes.
+    pub fn is_retryable(&self) -> bool {
+        self.kind() == MessageKind::Cooldown
+    }
+}
+
 impl FromStr for LuarmorMessage {
     type Err = Infallible;
 
@@ -124,11 +194,88 @@ impl<'de> Deserialize<'de> for LuarmorMessage {
         Ok(Self::from_str(s.as_str()).unwrap()) // Infallible
     }
 }
+impl Serialize for LuarmorMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+/// The category of a [LuarmorMessage].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum MessageKind {
+    /// The request succeeded.
+    Success,
+    /// The key, user or project does not exist.
+    NotFound,
+    /// The request clashes with an existing key, identifier or Discord ID.
+    Conflict,
+    /// The user is on cooldown, e.g. resetting their HWID too frequently.
+    Cooldown,
+    /// The API key is wrong or invalid.
+    AuthFailure,
+    /// The action is disabled for the project or script.
+    Disabled,
+    /// A value within the request is invalid, e.g. a malformed Discord ID.
+    ///
+    /// Kept apart from [MessageKind::Unknown] so the user can be told to fix their input, which neither clashes with anything nor succeeds if retried.
+    Invalid,
+    /// The message is not recognised.
+    Unknown,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct LuarmorResponse<T> {
     pub success: bool,
     pub message: LuarmorMessage,
     #[serde(flatten)]
     pub data: Option<T>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_classified() {
+        let cases = [
+            ("API is up and working!", MessageKind::Success),
+            ("Success!", MessageKind::Success),
+            ("User has been deleted!", MessageKind::Success),
+            ("Successfully reset!", MessageKind::Success),
+            ("Discord ID successfully linked!", MessageKind::Success),
+            ("User has been edited successfully!", MessageKind::Success),
+            ("Key not found", MessageKind::NotFound),
+            ("Project not found!", MessageKind::NotFound),
+            ("Project not found", MessageKind::NotFound),
+            ("User key doesn't exist", MessageKind::NotFound),
+            (
+                "This key already has a discord linked to it",
+                MessageKind::Conflict,
+            ),
+            ("Identifier already exists.", MessageKind::Conflict),
+            ("Discord ID already exists", MessageKind::Conflict),
+            ("Discord ID already exist.", MessageKind::Conflict),
+            ("User is on cooldown.", MessageKind::Cooldown),
+            (
+                "Invalid API key! Visit https://luarmor.net/ to get access.",
+                MessageKind::AuthFailure,
+            ),
+            ("Wrong API key", MessageKind::AuthFailure),
+            (
+                "Reset Hwid is disabled for this script",
+                MessageKind::Disabled,
+            ),
+            ("Invalid discord_id", MessageKind::Invalid),
+            ("nothing to see here.", MessageKind::Unknown),
+            ("Something new", MessageKind::Unknown),
+        ];
+        for (message, kind) in cases {
+            let message: LuarmorMessage = message.parse().unwrap();
+            assert_eq!(message.kind(), kind, "{message:?}");
+            assert_eq!(message.is_success(), kind == MessageKind::Success);
+            assert_eq!(message.is_not_found(), kind == MessageKind::NotFound);
+            assert_eq!(message.is_conflict(), kind == MessageKind::Conflict);
+            assert_eq!(message.is_retryable(), kind == MessageKind::Cooldown);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use api_builder::{Endpoint, api_endpoint};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// This will return you the version information about the API.
 ///
@@ -10,7 +10,7 @@ pub struct ApiStatus;
 impl Endpoint for ApiStatus {}
 
 /// [Reference](https://docs.luarmor.net/#tab-id-200-ok)
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize, Serialize)]
 pub struct ApiStatusResponse {
     pub version: String,
     pub active: bool,
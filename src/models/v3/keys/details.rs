@@ -1,8 +1,15 @@
+use std::{borrow::Cow, convert::Infallible, str::FromStr};
+
 use api_builder::{Endpoint, api_endpoint};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 
+use crate::{
+    ApiKey,
+    models::{DiscordId, ProjectId, ScriptId, owned},
+};
+
 /// You can get details of your API key.
 ///
 /// This includes:
@@ -16,40 +23,107 @@ use typed_builder::TypedBuilder;
 /// View full response at [ApiKeyDetailsResponse].
 ///
 /// [Reference](https://docs.luarmor.net/#getting-api-key-details)
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct ApiKeyDetails<'a> {
-    pub api_key: &'a str,
+    pub api_key: Cow<'a, ApiKey>,
 }
-#[api_endpoint(method = GET, path = format!("/v3/keys/{}/details", self.api_key))]
+#[api_endpoint(method = GET, path = format!("/v3/keys/{}/details", self.api_key.expose()))]
 impl Endpoint for ApiKeyDetails<'_> {}
+impl ApiKeyDetails<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> ApiKeyDetails<'static> {
+        ApiKeyDetails {
+            api_key: owned(self.api_key),
+        }
+    }
+}
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum KeyPlan {
     Basic,
     Premium,
     Pro,
-    Other(char),
+    /// A plan this crate doesn't know about yet.
+    Unknown(String),
+}
+impl KeyPlan {
+    /// The plan exactly as Luarmor sends it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Basic => "b",
+            Self::Premium => "p",
+            Self::Pro => "r",
+            Self::Unknown(x) => x.as_str(),
+        }
+    }
+}
+impl FromStr for KeyPlan {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "b" => Self::Basic,
+            "p" => Self::Premium,
+            "r" => Self::Pro,
+            s => Self::Unknown(s.to_string()),
+        })
+    }
 }
 impl<'de> Deserialize<'de> for KeyPlan {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        match char::deserialize(deserializer)? {
-            'b' => Ok(Self::Basic),
-            'p' => Ok(Self::Premium),
-            'r' => Ok(Self::Pro),
-            x => Ok(Self::Other(x)),
-        }
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::from_str(s.as_str()).unwrap()) // Infallible
+    }
+}
+impl Serialize for KeyPlan {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub enum ProjectPlatform {
     #[default]
     Roblox,
+    /// A platform this crate doesn't know about yet.
+    Unknown(String),
+}
+impl ProjectPlatform {
+    /// The platform exactly as Luarmor sends it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Roblox => "roblox",
+            Self::Unknown(x) => x.as_str(),
+        }
+    }
+}
+impl FromStr for ProjectPlatform {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "roblox" => Self::Roblox,
+            s => Self::Unknown(s.to_string()),
+        })
+    }
+}
+impl<'de> Deserialize<'de> for ProjectPlatform {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::from_str(s.as_str()).unwrap()) // Infallible
+    }
+}
+impl Serialize for ProjectPlatform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -65,47 +139,168 @@ impl<'de> Deserialize<'de> for ResetHwidCooldown {
         match i64::deserialize(deserializer)? {
             x if x < 0 => Ok(Self::Never),
             x => Ok(Self::Specified(
-                OffsetDateTime::from_unix_timestamp(x)
-                    .map_err(|err| serde::de::Error::custom(err))?,
+                OffsetDateTime::from_unix_timestamp(x).map_err(serde::de::Error::custom)?,
             )),
         }
     }
 }
+impl Serialize for ResetHwidCooldown {
+    /// [ResetHwidCooldown::Never] is sent as `-1`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Never => serializer.serialize_i64(-1),
+            Self::Specified(x) => serializer.serialize_i64(x.unix_timestamp()),
+        }
+    }
+}
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct ProjectSettings {
     pub reset_hwid_cooldown: ResetHwidCooldown,
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct Script {
     pub script_name: String,
-    pub script_id: String,
+    pub script_id: ScriptId,
     pub script_version: String,
     pub ffa: bool,
     pub silent: bool,
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct Project {
     pub platform: ProjectPlatform,
-    pub id: String,
+    pub id: ProjectId,
     pub name: String,
     pub settings: ProjectSettings,
     pub scripts: Vec<Script>,
 }
 
 /// [Reference](https://docs.luarmor.net/#tab-id-200-ok)
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct ApiKeyDetailsResponse {
     pub email: String,
-    pub discord_id: String,
+    #[serde(with = "crate::models::v3::empty_string")]
+    pub discord_id: Option<DiscordId>,
     #[serde(with = "time::serde::timestamp")]
     pub expires_at: OffsetDateTime,
     #[serde(with = "time::serde::timestamp")]
     pub registered_at: OffsetDateTime,
-    #[serde(deserialize_with = "crate::models::v3::deserialize_number_as_bool")]
+    #[serde(with = "crate::models::v3::number_as_bool")]
     pub enabled: bool,
     pub plan: KeyPlan,
     pub projects: Vec<Project>,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Deserializes `value`, checking it serializes back unchanged.
+    fn round_trip<T: DeserializeOwned + Serialize>(value: serde_json::Value) -> T {
+        let decoded: T = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), value);
+        decoded
+    }
+
+    fn assert_static<T: 'static>(value: T) -> T {
+        value
+    }
+
+    #[test]
+    fn owned_requests_keep_the_key_secret() {
+        let api_key = ApiKey::from("secret");
+        let request = assert_static(
+            ApiKeyDetails::builder()
+                .api_key(&api_key)
+                .build()
+                .into_owned(),
+        );
+        drop(api_key);
+
+        assert_eq!(request.api_key.expose(), "secret");
+        assert!(!format!("{request:?}").contains("secret"));
+    }
+
+    #[test]
+    fn key_plan_keeps_unknown_values() {
+        assert_eq!(round_trip::<KeyPlan>(json!("b")), KeyPlan::Basic);
+        assert_eq!(round_trip::<KeyPlan>(json!("p")), KeyPlan::Premium);
+        assert_eq!(round_trip::<KeyPlan>(json!("r")), KeyPlan::Pro);
+        assert_eq!(
+            round_trip::<KeyPlan>(json!("e")),
+            KeyPlan::Unknown("e".to_string())
+        );
+    }
+
+    #[test]
+    fn platform_keeps_unknown_values() {
+        assert_eq!(
+            round_trip::<ProjectPlatform>(json!("roblox")),
+            ProjectPlatform::Roblox
+        );
+        assert_eq!(
+            round_trip::<ProjectPlatform>(json!("fivem")),
+            ProjectPlatform::Unknown("fivem".to_string())
+        );
+    }
+
+    #[test]
+    fn reset_hwid_cooldown_round_trips() {
+        assert_eq!(
+            round_trip::<ResetHwidCooldown>(json!(-1)),
+            ResetHwidCooldown::Never
+        );
+        assert_eq!(
+            round_trip::<ResetHwidCooldown>(json!(1714521600)),
+            ResetHwidCooldown::Specified(OffsetDateTime::from_unix_timestamp(1714521600).unwrap())
+        );
+    }
+
+    #[test]
+    fn details_round_trip() {
+        let details = round_trip::<ApiKeyDetailsResponse>(json!({
+            "email": "dev@example.com",
+            "discord_id": "1030578127133286410",
+            "expires_at": 1900000000,
+            "registered_at": 1600000000,
+            "enabled": 1,
+            "plan": "p",
+            "projects": [
+                {
+                    "platform": "roblox",
+                    "id": "a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3",
+                    "name": "Hub",
+                    "settings": { "reset_hwid_cooldown": 86400 },
+                    "scripts": [
+                        {
+                            "script_name": "Main",
+                            "script_id": "f0e1d2c3b4a5968778695a4b3c2d1e0f",
+                            "script_version": "0.0.7",
+                            "ffa": false,
+                            "silent": true,
+                        },
+                    ],
+                },
+                {
+                    "platform": "roblox",
+                    "id": "0f1e2d3c4b5a69788796a5b4c3d2e1f0",
+                    "name": "Free",
+                    "settings": { "reset_hwid_cooldown": -1 },
+                    "scripts": [],
+                },
+            ],
+        }));
+
+        assert!(details.enabled);
+        assert_eq!(details.plan, KeyPlan::Premium);
+        assert_eq!(
+            details.projects[1].settings.reset_hwid_cooldown,
+            ResetHwidCooldown::Never
+        );
+    }
+}
@@ -1,8 +1,12 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 
+use crate::{ApiKey, models::owned};
+
 /// You can fetch the stats of your API key.
 ///
 /// This includes
@@ -17,35 +21,46 @@ use typed_builder::TypedBuilder;
 ///
 /// ⚠️ Authenticated ⚠️
 ///
-/// View full response at [ApiKeyStatsResponse].
+/// View full response at [ApiKeyStatsResponse], or [ApiKeyStatsNoUsersResponse] if `no_users` is set.
 ///
 /// [Reference](https://docs.luarmor.net/#getting-api-key-stats)
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct ApiKeyStats<'a> {
-    pub api_key: &'a str,
+    pub api_key: Cow<'a, ApiKey>,
 
     /// If `true`, it will return the info about user limits. (e.g. how many users there are, # of banned, # of whitelisted)
     #[builder(default = false)]
     pub no_users: bool,
 }
-#[api_endpoint(method = GET, path = format!("/v3/keys/{}/stats?noUsers={}", self.api_key, self.no_users))]
+#[api_endpoint(method = GET, path = format!("/v3/keys/{}/stats?noUsers={}", self.api_key.expose(), self.no_users))]
 impl Endpoint for ApiKeyStats<'_> {}
+impl ApiKeyStats<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> ApiKeyStats<'static> {
+        ApiKeyStats {
+            api_key: owned(self.api_key),
+            no_users: self.no_users,
+        }
+    }
+}
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize, Serialize)]
 pub struct ExecutionData {
     pub frequency: u32,
     pub executions: Vec<u32>,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize, Serialize,
+)]
 pub struct ScriptDefaultStats {
     pub scripts: u8,
     pub users: u32,
     pub obfuscations: u32,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct ScriptStats {
     pub obfuscations: u32,
     pub scripts: u8,
@@ -56,7 +71,7 @@ pub struct ScriptStats {
     pub reset_at: OffsetDateTime,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct ScriptStatsNoUsers {
     pub obfuscations: u32,
     pub scripts: u8,
@@ -67,15 +82,122 @@ pub struct ScriptStatsNoUsers {
 }
 
 /// [Reference](https://docs.luarmor.net/#tab-id-200-ok)
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct ApiKeyStatsResponse {
     pub execution_data: ExecutionData,
     pub stats: ScriptStats,
 }
 
 /// [Reference](https://docs.luarmor.net/#tab-id-200-ok)
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct ApiKeyStatsNoUsersResponse {
     pub execution_data: ExecutionData,
     pub stats: ScriptStatsNoUsers,
 }
+
+#[cfg(test)]
+mod tests {
+    use api_builder::Bytes;
+    use http::Response;
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::{Luarmor, LuarmorError};
+
+    const STATS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/stats.json"
+    ));
+    const STATS_NO_USERS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/stats_no_users.json"
+    ));
+
+    fn decode<T: DeserializeOwned>(
+        fixture: &'static [u8],
+        no_users: bool,
+    ) -> Result<T, LuarmorError> {
+        let endpoint = ApiKeyStats::builder()
+            .api_key(ApiKey::from("key"))
+            .no_users(no_users)
+            .build();
+        Luarmor(endpoint).decode(Response::new(Bytes::from_static(fixture)))
+    }
+
+    fn execution_data() -> ExecutionData {
+        ExecutionData {
+            frequency: 1800,
+            executions: vec![12, 40, 0, 7, 93, 18],
+        }
+    }
+
+    const DEFAULT: ScriptDefaultStats = ScriptDefaultStats {
+        scripts: 5,
+        users: 5000,
+        obfuscations: 200,
+    };
+
+    #[test]
+    fn decodes_stats() {
+        let stats: ApiKeyStatsResponse = decode(STATS, false).unwrap();
+        assert_eq!(
+            stats,
+            ApiKeyStatsResponse {
+                execution_data: execution_data(),
+                stats: ScriptStats {
+                    obfuscations: 2,
+                    scripts: 3,
+                    users: 1041,
+                    attacks_blocked: 25,
+                    default: DEFAULT,
+                    reset_at: OffsetDateTime::from_unix_timestamp(1714521600).unwrap(),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_stats_no_users() {
+        let stats: ApiKeyStatsNoUsersResponse = decode(STATS_NO_USERS, true).unwrap();
+        assert_eq!(
+            stats,
+            ApiKeyStatsNoUsersResponse {
+                execution_data: execution_data(),
+                stats: ScriptStatsNoUsers {
+                    obfuscations: 2,
+                    scripts: 3,
+                    attacks_blocked: 25,
+                    default: DEFAULT,
+                    reset_at: OffsetDateTime::from_unix_timestamp(1714521600).unwrap(),
+                },
+            }
+        );
+    }
+
+    // `stats.users` is only sent when `noUsers` isn't set
+    #[test]
+    fn stats_require_users() {
+        let err = decode::<ApiKeyStatsResponse>(STATS_NO_USERS, false).unwrap_err();
+        assert!(
+            matches!(&err, LuarmorError::Decode { path, .. } if path == "stats"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn stats_round_trip() {
+        for (fixture, no_users) in [(STATS, false), (STATS_NO_USERS, true)] {
+            let mut expected: serde_json::Value = serde_json::from_slice(fixture).unwrap();
+            let expected = expected.as_object_mut().unwrap();
+            expected.remove("success");
+            expected.remove("message");
+
+            let value = if no_users {
+                serde_json::to_value(decode::<ApiKeyStatsNoUsersResponse>(fixture, true).unwrap())
+            } else {
+                serde_json::to_value(decode::<ApiKeyStatsResponse>(fixture, false).unwrap())
+            };
+            assert_eq!(value.unwrap().as_object(), Some(&*expected));
+        }
+    }
+}
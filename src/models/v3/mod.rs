@@ -1,14 +1,46 @@
-use serde::{Deserialize, Deserializer};
-
 pub mod keys;
 pub mod projects;
 
-pub(crate) fn deserialize_number_as_bool<'a, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: Deserializer<'a>,
-{
-    match u8::deserialize(deserializer)? {
-        0 => Ok(false),
-        _ => Ok(true),
+/// An optional value which Luarmor sends as an empty string when missing.
+pub(crate) mod empty_string {
+    use serde::{Deserialize, Deserializer, Serializer, de::IntoDeserializer};
+
+    pub(crate) fn deserialize<'a, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'a>,
+        T: Deserialize<'a>,
+    {
+        let value = String::deserialize(deserializer)?;
+        if value.is_empty() {
+            return Ok(None);
+        }
+        T::deserialize(value.into_deserializer()).map(Some)
+    }
+
+    pub(crate) fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<str>,
+    {
+        serializer.serialize_str(value.as_ref().map_or("", AsRef::as_ref))
+    }
+}
+
+/// A boolean which Luarmor sends as `0` or `1`.
+pub(crate) mod number_as_bool {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn deserialize<'a, D>(deserializer: D) -> Result<bool, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        match u8::deserialize(deserializer)? {
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    pub(crate) fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*value))
     }
 }
@@ -1,7 +1,11 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
 use serde::Serialize;
 use typed_builder::TypedBuilder;
 
+use crate::models::{ProjectId, ScriptId, owned};
+
 /// Programmatically edit a script.
 ///
 /// ⚠️ Authenticated ⚠️
@@ -9,17 +13,15 @@ use typed_builder::TypedBuilder;
 /// View full response at [UpdateScriptResponse].
 ///
 /// [Reference](https://docs.luarmor.net/#updating-a-script)
-#[derive(
-    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, TypedBuilder,
-)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct UpdateScript<'a> {
     /// The associated project ID that contains the script.
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
     /// The id of the script to update.
-    pub script_id: &'a str,
+    pub script_id: Cow<'a, ScriptId>,
     /// The raw Lua source code to upload.
-    pub script: &'a str,
+    pub script: Cow<'a, str>,
 
     /// Disables Luarmor console outputs.
     /// This is not recommended as it's useful for debugging.
@@ -42,3 +44,58 @@ pub struct UpdateScript<'a> {
 }
 #[api_endpoint(method = PUT, path = format!("/v3/projects/{}/scripts/{}", self.project_id, self.script_id), self_as_body = "application/json")]
 impl Endpoint for UpdateScript<'_> {}
+impl<'a> UpdateScript<'a> {
+    /// Uploads `upload` to `script_id` within `project_id`.
+    pub fn new(
+        project_id: impl Into<Cow<'a, ProjectId>>,
+        script_id: impl Into<Cow<'a, ScriptId>>,
+        upload: ScriptUpload<'a>,
+    ) -> Self {
+        Self {
+            project_id: project_id.into(),
+            script_id: script_id.into(),
+            script: upload.script,
+            silent: upload.silent,
+            ffa: upload.ffa,
+            heartbeat: upload.heartbeat,
+            lightning: upload.lightning,
+        }
+    }
+}
+impl UpdateScript<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> UpdateScript<'static> {
+        UpdateScript {
+            project_id: owned(self.project_id),
+            script_id: owned(self.script_id),
+            script: owned(self.script),
+            silent: self.silent,
+            ffa: self.ffa,
+            heartbeat: self.heartbeat,
+            lightning: self.lightning,
+        }
+    }
+}
+
+/// A script's new source and settings, without the project or script they apply to.
+///
+/// Used where the script is already known, e.g. [crate::ScriptHandle::update].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct ScriptUpload<'a> {
+    /// See [UpdateScript::script].
+    pub script: Cow<'a, str>,
+
+    /// See [UpdateScript::silent].
+    #[builder(default, setter(strip_option))]
+    pub silent: Option<bool>,
+    /// See [UpdateScript::ffa].
+    #[builder(default, setter(strip_option))]
+    pub ffa: Option<bool>,
+    /// See [UpdateScript::heartbeat].
+    #[builder(default = Some(true), setter(strip_option))]
+    pub heartbeat: Option<bool>,
+    /// See [UpdateScript::lightning].
+    #[builder(default, setter(strip_option))]
+    pub lightning: Option<bool>,
+}
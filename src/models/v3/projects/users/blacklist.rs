@@ -1,27 +1,29 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
 use serde::Serialize;
 use typed_builder::TypedBuilder;
 
+use crate::models::{ProjectId, UserKey, owned};
+
 /// This will blacklist an existing key, and the HWID linked to it (if any).
 ///
 /// ⚠️ Authenticated ⚠️
 ///
 /// [Reference](https://docs.luarmor.net/#blacklisting-a-key)
-#[derive(
-    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, TypedBuilder,
-)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct BlacklistUser<'a> {
     /// The associated project ID that contains the key.
     #[serde(skip)]
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
 
     /// The user key to blacklist.
-    pub user_key: &'a str,
+    pub user_key: Cow<'a, UserKey>,
     /// The reason of the blacklist.
     /// This will be shown to the user when they execute.
     #[builder(default, setter(strip_option))]
-    pub ban_reason: Option<&'a str>,
+    pub ban_reason: Option<Cow<'a, str>>,
     /// The **exact** unix timestamp of the blacklist expiry date.
     ///
     /// NOTE: a value of less than 0 will result in an infinite ban
@@ -30,3 +32,14 @@ pub struct BlacklistUser<'a> {
 }
 #[api_endpoint(method = POST, path = format!("/v3/projects/{}/users/blacklist", self.project_id), self_as_body = "application/json")]
 impl Endpoint for BlacklistUser<'_> {}
+impl BlacklistUser<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> BlacklistUser<'static> {
+        BlacklistUser {
+            project_id: owned(self.project_id),
+            user_key: owned(self.user_key),
+            ban_reason: self.ban_reason.map(owned),
+            ban_expire: self.ban_expire,
+        }
+    }
+}
@@ -1,8 +1,12 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 
+use crate::models::{DiscordId, Hwid, ProjectId, UserKey, owned};
+
 /// This will generate a new key.
 ///
 /// If the parameters aren't specified, the key will be 'unassigned'.
@@ -24,48 +28,308 @@ use typed_builder::TypedBuilder;
 /// If you don't provide `key_days` and provide `auth_expire` directly, you must include one of `identifier` or `discord_id` parameters to tell the server that it is a claimed key, so it will start counting towards their remaining days instantly.
 /// If you don't provide `identifier` or `discord_id` fields, it will automatically convert the offset between current time and `auth_expire` to `key_days`.
 ///
-/// TODO: consider making a separate struct to ensure the above case doesn't happen...
+/// [NewKey] only allows the valid combinations: [LifetimeKey], [UnclaimedKey] and [ClaimedKey].
 ///
 /// ⚠️ Authenticated ⚠️
 ///
 /// [Reference](https://docs.luarmor.net/#creating-a-key-user)
-#[derive(
-    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, TypedBuilder,
-)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct CreateUser<'a> {
     /// The associated project ID that you want to add the user key to.
     #[serde(skip)]
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
 
-    /// A unique identifier for the key, usually their HWID.
-    pub identifier: Option<&'a str>,
-    /// The unix timestamp of when the key should expire.
-    ///
-    /// NOTE: if this is not defined, the key will never expire; read the struct comments for more information.
-    #[serde(with = "time::serde::timestamp::option")]
-    #[builder(default, setter(strip_option))]
-    pub auth_expire: Option<OffsetDateTime>,
+    /// The kind of key to create, and who it belongs to.
+    #[serde(flatten)]
+    #[builder(default)]
+    pub key: NewKey<'a>,
     /// A custom note for the key, which can be used to identify the user and key or provide additional information about them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    pub note: Option<Cow<'a, str>>,
+}
+#[api_endpoint(method = POST, path = format!("/v3/projects/{}/users", self.project_id), self_as_body = "application/json")]
+impl Endpoint for CreateUser<'_> {}
+impl<'a> CreateUser<'a> {
+    /// Creates `user` within `project_id`.
+    pub fn new(project_id: impl Into<Cow<'a, ProjectId>>, user: NewUser<'a>) -> Self {
+        Self {
+            project_id: project_id.into(),
+            key: user.key,
+            note: user.note,
+        }
+    }
+}
+impl CreateUser<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> CreateUser<'static> {
+        CreateUser {
+            project_id: owned(self.project_id),
+            key: self.key.into_owned(),
+            note: self.note.map(owned),
+        }
+    }
+}
+
+/// A key to create, without the project it's created in.
+///
+/// Used where the project is already known, e.g. [crate::ProjectHandle::create_user].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct NewUser<'a> {
+    /// See [CreateUser::key].
+    #[builder(default)]
+    pub key: NewKey<'a>,
+    /// See [CreateUser::note].
     #[builder(default, setter(strip_option))]
-    pub note: Option<&'a str>,
+    pub note: Option<Cow<'a, str>>,
+}
+impl NewUser<'_> {
+    /// Clones any borrowed fields, e.g. to queue the user or move it into another task.
+    pub fn into_owned(self) -> NewUser<'static> {
+        NewUser {
+            key: self.key.into_owned(),
+            note: self.note.map(owned),
+        }
+    }
+}
+
+/// Who a key belongs to, at least one of the two must be known.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum KeyOwner<'a> {
+    /// A unique identifier for the key, usually their HWID.
+    Identifier(Cow<'a, Hwid>),
     /// The Discord ID associated with the key.
-    /// However if you set up the Discord bot, the user can manually redeem their key and link it via the "Redeem" button on the panel.
     ///
     /// NOTE: if a Discord ID is not associated with a key, the user cannot use the /resethwid discord command.
-    #[builder(default, setter(strip_option))]
-    pub discord_id: Option<&'a str>,
+    DiscordId(Cow<'a, DiscordId>),
+    Both {
+        identifier: Cow<'a, Hwid>,
+        discord_id: Cow<'a, DiscordId>,
+    },
+}
+impl KeyOwner<'_> {
+    /// The identifier, unless the key only belongs to a Discord ID.
+    pub fn identifier(&self) -> Option<&Hwid> {
+        match self {
+            Self::Identifier(x) | Self::Both { identifier: x, .. } => Some(x),
+            Self::DiscordId(_) => None,
+        }
+    }
+
+    /// The Discord ID, unless the key only belongs to an identifier.
+    pub fn discord_id(&self) -> Option<&DiscordId> {
+        match self {
+            Self::DiscordId(x) | Self::Both { discord_id: x, .. } => Some(x),
+            Self::Identifier(_) => None,
+        }
+    }
+
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> KeyOwner<'static> {
+        match self {
+            Self::Identifier(x) => KeyOwner::Identifier(owned(x)),
+            Self::DiscordId(x) => KeyOwner::DiscordId(owned(x)),
+            Self::Both {
+                identifier,
+                discord_id,
+            } => KeyOwner::Both {
+                identifier: owned(identifier),
+                discord_id: owned(discord_id),
+            },
+        }
+    }
+}
+
+/// A key which never expires.
+///
+/// Without an owner, the key is unassigned and the first user to redeem it claims it.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct LifetimeKey<'a> {
+    pub owner: Option<KeyOwner<'a>>,
+}
+impl LifetimeKey<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> LifetimeKey<'static> {
+        LifetimeKey {
+            owner: self.owner.map(KeyOwner::into_owned),
+        }
+    }
+}
+
+/// An unassigned key which only starts counting down once it has been redeemed.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct UnclaimedKey {
     /// The number of days the key will have **after** it has been activated by a user.
-    ///
-    /// For more information, read the struct comments.
-    #[builder(default, setter(strip_option))]
-    pub key_days: Option<u32>,
+    pub key_days: u32,
+}
+
+/// A key which already belongs to someone and expires at a fixed time.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ClaimedKey<'a> {
+    pub owner: KeyOwner<'a>,
+    /// When the key expires.
+    pub auth_expire: OffsetDateTime,
+}
+impl ClaimedKey<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> ClaimedKey<'static> {
+        ClaimedKey {
+            owner: self.owner.into_owned(),
+            auth_expire: self.auth_expire,
+        }
+    }
+}
+
+/// The kind of key [CreateUser] creates, see its docs for the differences.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum NewKey<'a> {
+    Lifetime(LifetimeKey<'a>),
+    Unclaimed(UnclaimedKey),
+    Claimed(ClaimedKey<'a>),
+}
+impl NewKey<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> NewKey<'static> {
+        match self {
+            Self::Lifetime(x) => NewKey::Lifetime(x.into_owned()),
+            Self::Unclaimed(x) => NewKey::Unclaimed(x),
+            Self::Claimed(x) => NewKey::Claimed(x.into_owned()),
+        }
+    }
+}
+impl Default for NewKey<'_> {
+    /// An unassigned lifetime key.
+    fn default() -> Self {
+        Self::Lifetime(LifetimeKey::default())
+    }
+}
+impl<'a> From<LifetimeKey<'a>> for NewKey<'a> {
+    fn from(value: LifetimeKey<'a>) -> Self {
+        Self::Lifetime(value)
+    }
+}
+impl From<UnclaimedKey> for NewKey<'_> {
+    fn from(value: UnclaimedKey) -> Self {
+        Self::Unclaimed(value)
+    }
+}
+impl<'a> From<ClaimedKey<'a>> for NewKey<'a> {
+    fn from(value: ClaimedKey<'a>) -> Self {
+        Self::Claimed(value)
+    }
+}
+impl Serialize for NewKey<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Wire<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            identifier: Option<&'a Hwid>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            discord_id: Option<&'a DiscordId>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            auth_expire: Option<i64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            key_days: Option<u32>,
+        }
+
+        let (owner, auth_expire, key_days) = match self {
+            Self::Lifetime(key) => (key.owner.as_ref(), None, None),
+            Self::Unclaimed(key) => (None, None, Some(key.key_days)),
+            Self::Claimed(key) => (
+                Some(&key.owner),
+                Some(key.auth_expire.unix_timestamp()),
+                None,
+            ),
+        };
+        Wire {
+            identifier: owner.and_then(|x| x.identifier()),
+            discord_id: owner.and_then(|x| x.discord_id()),
+            auth_expire,
+            key_days,
+        }
+        .serialize(serializer)
+    }
 }
-#[api_endpoint(method = POST, path = format!("/v3/projects/{}/users", self.project_id), self_as_body = "application/json")]
-impl Endpoint for CreateUser<'_> {}
 
 /// [Reference](https://docs.luarmor.net/#tab-id-200-ok-key-has-been-added-successfully)
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct CreateUserResponse {
-    pub user_key: String,
+    pub user_key: UserKey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(key: impl Into<NewKey<'static>>) -> String {
+        let payload = CreateUser::builder()
+            .project_id(ProjectId::new("project").unwrap())
+            .key(key)
+            .build();
+        let (content_type, body) = payload.body().unwrap().unwrap();
+        assert_eq!(content_type, "application/json");
+        String::from_utf8(body).unwrap()
+    }
+
+    /// Each kind of owner, with the fields it sends.
+    fn owners() -> [(KeyOwner<'static>, &'static str); 3] {
+        let identifier = || Hwid::new("hwid").unwrap().into();
+        let discord_id = || DiscordId::new("1234").unwrap().into();
+        [
+            (KeyOwner::Identifier(identifier()), r#""identifier":"hwid""#),
+            (KeyOwner::DiscordId(discord_id()), r#""discord_id":"1234""#),
+            (
+                KeyOwner::Both {
+                    identifier: identifier(),
+                    discord_id: discord_id(),
+                },
+                r#""identifier":"hwid","discord_id":"1234""#,
+            ),
+        ]
+    }
+
+    #[test]
+    fn unassigned_lifetime_keys_are_empty() {
+        assert_eq!(body(LifetimeKey::default()), "{}");
+    }
+
+    #[test]
+    fn lifetime_keys_send_their_owner() {
+        for (owner, fields) in owners() {
+            let key = LifetimeKey { owner: Some(owner) };
+            assert_eq!(body(key), format!("{{{fields}}}"));
+        }
+    }
+
+    #[test]
+    fn unclaimed_keys_send_their_days() {
+        assert_eq!(body(UnclaimedKey { key_days: 30 }), r#"{"key_days":30}"#);
+    }
+
+    #[test]
+    fn claimed_keys_send_their_owner_and_expiry() {
+        for (owner, fields) in owners() {
+            let key = ClaimedKey {
+                owner,
+                auth_expire: OffsetDateTime::from_unix_timestamp(1700000000).unwrap(),
+            };
+            assert_eq!(
+                body(key),
+                format!(r#"{{{fields},"auth_expire":1700000000}}"#)
+            );
+        }
+    }
+
+    #[test]
+    fn notes_are_sent_with_the_key() {
+        let payload = CreateUser::builder()
+            .project_id(ProjectId::new("project").unwrap())
+            .key(UnclaimedKey { key_days: 1 })
+            .note("note")
+            .build();
+        let (_, body) = payload.body().unwrap().unwrap();
+        assert_eq!(body, br#"{"key_days":1,"note":"note"}"#);
+    }
 }
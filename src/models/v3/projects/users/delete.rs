@@ -1,19 +1,32 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
 use typed_builder::TypedBuilder;
 
+use crate::models::{ProjectId, UserKey, owned};
+
 /// This will delete an existing key, effectively removing the access of the user who has their HWID and Discord ID linked to that key.
 ///
 /// ⚠️ Authenticated ⚠️
 ///
 /// [Reference](https://docs.luarmor.net/#deleting-the-key)
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct DeleteUser<'a> {
     /// The associated project ID that contains the key.
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
 
     /// The user key to delete.
-    pub user_key: &'a str,
+    pub user_key: Cow<'a, UserKey>,
 }
 #[api_endpoint(method = DELETE, path = format!("/v3/projects/{}/users?user_key={}", self.project_id, self.user_key))]
 impl Endpoint for DeleteUser<'_> {}
+impl DeleteUser<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> DeleteUser<'static> {
+        DeleteUser {
+            project_id: owned(self.project_id),
+            user_key: owned(self.user_key),
+        }
+    }
+}
@@ -1,8 +1,15 @@
+use std::{borrow::Cow, convert::Infallible, str::FromStr};
+
 use api_builder::{Endpoint, QueryParamPair, QueryParamPairs, api_endpoint};
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 
+use crate::models::{
+    DiscordId, Hwid, ProjectId, UnbanToken, UserKey, owned,
+    v3::{empty_string, number_as_bool},
+};
+
 /// This will fetch **all** filtered users within a project.
 ///
 /// NOTE: if you want to get someone's `user_key` from their `discord_id`, they must have linked their key first.
@@ -12,28 +19,26 @@ use typed_builder::TypedBuilder;
 /// ⚠️ Authenticated ⚠️
 ///
 /// [Reference](https://docs.luarmor.net/#getting-users)
-#[derive(
-    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, TypedBuilder,
-)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct GetUsers<'a> {
     /// The associated project ID that contains the key.
     #[serde(skip)]
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
 
     /// Filter for a specific user, based upon their key.
     #[builder(default, setter(strip_option))]
-    pub user_key: Option<&'a str>,
+    pub user_key: Option<Cow<'a, UserKey>>,
     /// Filter for a specific user, based upon their Discord ID.
     #[builder(default, setter(strip_option))]
-    pub discord_id: Option<&'a str>,
+    pub discord_id: Option<Cow<'a, DiscordId>>,
     /// Filter for a specific user, based upon their identifier (HWID).
     #[builder(default, setter(strip_option))]
-    pub identifier: Option<&'a str>,
+    pub identifier: Option<Cow<'a, Hwid>>,
 
     /// Filter based upon a search string that can be found anywhere, i.e. `identifier`, `user_key`, `discord_id`, `note`.
     #[builder(default, setter(strip_option))]
-    pub search: Option<&'a str>,
+    pub search: Option<Cow<'a, str>>,
 
     /// Pagination start index, not page.
     #[builder(default, setter(strip_option))]
@@ -47,16 +52,16 @@ impl Endpoint for GetUsers<'_> {
     fn query_params(&self) -> Option<QueryParamPairs> {
         let mut params = QueryParamPairs::default();
 
-        if let Some(x) = self.user_key {
+        if let Some(x) = &self.user_key {
             params.push(QueryParamPair::new("user_key", x.to_string()));
         }
-        if let Some(x) = self.discord_id {
+        if let Some(x) = &self.discord_id {
             params.push(QueryParamPair::new("discord_id", x.to_string()));
         }
-        if let Some(x) = self.identifier {
+        if let Some(x) = &self.identifier {
             params.push(QueryParamPair::new("identifier", x.to_string()));
         }
-        if let Some(x) = self.search {
+        if let Some(x) = &self.search {
             params.push(QueryParamPair::new("search", x.to_string()));
         }
         if let Some(x) = self.from {
@@ -69,50 +74,157 @@ impl Endpoint for GetUsers<'_> {
         Some(params)
     }
 }
-
-fn deserialize_empty_string<'a, D>(deserializer: D) -> Result<Option<String>, D::Error>
-where
-    D: Deserializer<'a>,
-{
-    let x = String::deserialize(deserializer)?;
-    match x.as_str() {
-        "" => Ok(None),
-        _ => Ok(Some(x)),
+impl<'a> GetUsers<'a> {
+    /// Fetches the users within `project_id` which match `filter`.
+    pub fn new(project_id: impl Into<Cow<'a, ProjectId>>, filter: UserFilter<'a>) -> Self {
+        Self {
+            project_id: project_id.into(),
+            user_key: filter.user_key,
+            discord_id: filter.discord_id,
+            identifier: filter.identifier,
+            search: filter.search,
+            from: filter.from,
+            until: filter.until,
+        }
     }
 }
+impl GetUsers<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> GetUsers<'static> {
+        GetUsers {
+            project_id: owned(self.project_id),
+            user_key: self.user_key.map(owned),
+            discord_id: self.discord_id.map(owned),
+            identifier: self.identifier.map(owned),
+            search: self.search.map(owned),
+            from: self.from,
+            until: self.until,
+        }
+    }
+}
+
+/// Which users to fetch, without the project they're in.
+///
+/// Used where the project is already known, e.g. [crate::ProjectHandle::users]. The [Default] matches every user.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[builder(field_defaults(setter(into)))]
+pub struct UserFilter<'a> {
+    /// See [GetUsers::user_key].
+    #[builder(default, setter(strip_option))]
+    pub user_key: Option<Cow<'a, UserKey>>,
+    /// See [GetUsers::discord_id].
+    #[builder(default, setter(strip_option))]
+    pub discord_id: Option<Cow<'a, DiscordId>>,
+    /// See [GetUsers::identifier].
+    #[builder(default, setter(strip_option))]
+    pub identifier: Option<Cow<'a, Hwid>>,
+    /// See [GetUsers::search].
+    #[builder(default, setter(strip_option))]
+    pub search: Option<Cow<'a, str>>,
+    /// See [GetUsers::from].
+    #[builder(default, setter(strip_option))]
+    pub from: Option<u32>,
+    /// See [GetUsers::until].
+    #[builder(default, setter(strip_option))]
+    pub until: Option<u32>,
+}
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub enum UserIdentifierType {
     HWID,
     #[default]
     None,
+    /// A type this crate doesn't know about yet.
+    Unknown(String),
+}
+impl UserIdentifierType {
+    /// The type exactly as Luarmor sends it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::HWID => "HWID",
+            Self::None => "",
+            Self::Unknown(x) => x.as_str(),
+        }
+    }
+}
+impl FromStr for UserIdentifierType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "HWID" => Self::HWID,
+            "" => Self::None,
+            s => Self::Unknown(s.to_string()),
+        })
+    }
 }
 impl<'de> Deserialize<'de> for UserIdentifierType {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        match String::deserialize(deserializer)?.as_str() {
-            "HWID" => Ok(Self::HWID),
-            _ => Ok(Self::None),
-        }
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::from_str(s.as_str()).unwrap()) // Infallible
+    }
+}
+impl Serialize for UserIdentifierType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
     }
 }
 
 /// Each key will have a `status` field.
 ///
 /// - [UserStatus::Active]
-///     The user has linked their HWID to the key and it is active.
+///   The user has linked their HWID to the key and it is active.
 /// - [UserStatus::Reset]
-///     The user has reset their HWID and it's waiting to be assigned, upontheir first execution.
+///   The user has reset their HWID and it's waiting to be assigned, upontheir first execution.
 /// - [UserStatus::Banned]
-///     The user does not have a key linked and banned.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
+///   The user does not have a key linked and banned.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum UserStatus {
     Active,
     Reset,
     Banned,
+    /// A status this crate doesn't know about yet.
+    Unknown(String),
+}
+impl UserStatus {
+    /// The status exactly as Luarmor sends it.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Active => "active",
+            Self::Reset => "reset",
+            Self::Banned => "banned",
+            Self::Unknown(x) => x.as_str(),
+        }
+    }
+}
+impl FromStr for UserStatus {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "active" => Self::Active,
+            "reset" => Self::Reset,
+            "banned" => Self::Banned,
+            s => Self::Unknown(s.to_string()),
+        })
+    }
+}
+impl<'de> Deserialize<'de> for UserStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::from_str(s.as_str()).unwrap()) // Infallible
+    }
+}
+impl Serialize for UserStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -128,44 +240,210 @@ impl<'de> Deserialize<'de> for UserExpiration {
         match i64::deserialize(deserializer)? {
             x if x < 0 => Ok(Self::Never),
             x => Ok(Self::Specified(
-                OffsetDateTime::from_unix_timestamp(x)
-                    .map_err(|err| serde::de::Error::custom(err))?,
+                OffsetDateTime::from_unix_timestamp(x).map_err(serde::de::Error::custom)?,
             )),
         }
     }
 }
+impl Serialize for UserExpiration {
+    /// [UserExpiration::Never] is sent as `-1`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Never => serializer.serialize_i64(-1),
+            Self::Specified(x) => serializer.serialize_i64(x.unix_timestamp()),
+        }
+    }
+}
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 pub struct User {
-    #[serde(deserialize_with = "deserialize_empty_string")]
-    pub user_key: Option<String>,
-    #[serde(deserialize_with = "deserialize_empty_string")]
-    pub identifier: Option<String>,
+    #[serde(with = "empty_string")]
+    pub user_key: Option<UserKey>,
+    #[serde(with = "empty_string")]
+    pub identifier: Option<Hwid>,
     pub identifier_type: UserIdentifierType,
-    #[serde(deserialize_with = "deserialize_empty_string")]
-    pub discord_id: Option<String>,
+    #[serde(with = "empty_string")]
+    pub discord_id: Option<DiscordId>,
     pub status: UserStatus,
     #[serde(with = "time::serde::timestamp")]
     pub last_reset: OffsetDateTime,
     pub total_resets: u32,
     pub auth_expire: UserExpiration,
-    #[serde(deserialize_with = "crate::models::v3::deserialize_number_as_bool")]
+    #[serde(with = "number_as_bool")]
     pub banned: bool,
-    #[serde(deserialize_with = "deserialize_empty_string")]
+    #[serde(with = "empty_string")]
     pub ban_reason: Option<String>,
     #[serde(with = "time::serde::timestamp")]
     pub ban_expire: OffsetDateTime,
-    #[serde(deserialize_with = "deserialize_empty_string")]
-    pub unban_token: Option<String>,
+    #[serde(with = "empty_string")]
+    pub unban_token: Option<UnbanToken>,
     pub total_executions: u64,
-    #[serde(deserialize_with = "deserialize_empty_string")]
+    #[serde(with = "empty_string")]
     pub note: Option<String>,
-    #[serde(deserialize_with = "deserialize_empty_string")]
+    #[serde(with = "empty_string")]
     pub ban_ip: Option<String>,
 }
 
 /// [Reference](https://docs.luarmor.net/#tab-id-200-ok-success-2)
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize, Serialize)]
 pub struct GetUsersResponse {
     pub users: Vec<User>,
 }
+
+/// An active lifetime key as Luarmor sends it, with each field in `overrides` replaced.
+#[cfg(test)]
+pub(crate) fn test_user(overrides: serde_json::Value) -> User {
+    let mut user = serde_json::json!({
+        "user_key": "key1",
+        "identifier": "",
+        "identifier_type": "",
+        "discord_id": "",
+        "status": "active",
+        "last_reset": 0,
+        "total_resets": 0,
+        "auth_expire": -1,
+        "banned": 0,
+        "ban_reason": "",
+        "ban_expire": -1,
+        "unban_token": "",
+        "total_executions": 0,
+        "note": "",
+        "ban_ip": "",
+    });
+    for (field, value) in overrides.as_object().unwrap() {
+        user[field] = value.clone();
+    }
+    serde_json::from_value(user).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Deserializes `value`, checking it serializes back unchanged.
+    fn round_trip<T: DeserializeOwned + Serialize>(value: serde_json::Value) -> T {
+        let decoded: T = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), value);
+        decoded
+    }
+
+    #[tokio::test]
+    async fn owned_requests_can_be_moved_into_tasks() {
+        let project_id = ProjectId::new("project").unwrap();
+        let discord_id = DiscordId::new("1234").unwrap();
+        let request = GetUsers::builder()
+            .project_id(&project_id)
+            .discord_id(&discord_id)
+            .build()
+            .into_owned();
+        drop((project_id, discord_id));
+
+        // Only compiles if nothing is borrowed any more
+        let request = tokio::spawn(async move { request }).await.unwrap();
+        assert_eq!(request.project_id.as_str(), "project");
+    }
+
+    #[test]
+    fn user_status_keeps_unknown_values() {
+        assert_eq!(
+            round_trip::<UserStatus>(json!("active")),
+            UserStatus::Active
+        );
+        assert_eq!(round_trip::<UserStatus>(json!("reset")), UserStatus::Reset);
+        assert_eq!(
+            round_trip::<UserStatus>(json!("banned")),
+            UserStatus::Banned
+        );
+        assert_eq!(
+            round_trip::<UserStatus>(json!("suspended")),
+            UserStatus::Unknown("suspended".to_string())
+        );
+    }
+
+    #[test]
+    fn identifier_type_keeps_unknown_values() {
+        assert_eq!(
+            round_trip::<UserIdentifierType>(json!("HWID")),
+            UserIdentifierType::HWID
+        );
+        assert_eq!(
+            round_trip::<UserIdentifierType>(json!("")),
+            UserIdentifierType::None
+        );
+        assert_eq!(
+            round_trip::<UserIdentifierType>(json!("IP")),
+            UserIdentifierType::Unknown("IP".to_string())
+        );
+    }
+
+    #[test]
+    fn user_expiration_round_trips() {
+        assert_eq!(
+            round_trip::<UserExpiration>(json!(-1)),
+            UserExpiration::Never
+        );
+        assert_eq!(
+            round_trip::<UserExpiration>(json!(1800000000)),
+            UserExpiration::Specified(OffsetDateTime::from_unix_timestamp(1800000000).unwrap())
+        );
+    }
+
+    #[test]
+    fn user_round_trips() {
+        let users = round_trip::<GetUsersResponse>(json!({
+            "users": [
+                {
+                    "user_key": "GhVDxfYKzrJnvWxeXZmMXhuTJfCsqTkC",
+                    "identifier": "5ad3f2b4c1e04c4b9b2f2b6a0f0c1d2e",
+                    "identifier_type": "HWID",
+                    "discord_id": "1030578127133286410",
+                    "status": "active",
+                    "last_reset": 1714521600,
+                    "total_resets": 3,
+                    "auth_expire": 1800000000,
+                    "banned": 1,
+                    "ban_reason": "Sharing keys",
+                    "ban_expire": 1720000000,
+                    "unban_token": "KAfbWzStkQYvCNXhqjLeUpMmRTgdnrsx",
+                    "total_executions": 212,
+                    "note": "Reseller",
+                    "ban_ip": "203.0.113.7",
+                },
+                {
+                    "user_key": "ZkVnbQcYfSPtHaWrxdmLgUeJsCKNpoTq",
+                    "identifier": "",
+                    "identifier_type": "",
+                    "discord_id": "",
+                    "status": "reset",
+                    "last_reset": 0,
+                    "total_resets": 0,
+                    "auth_expire": -1,
+                    "banned": 0,
+                    "ban_reason": "",
+                    "ban_expire": -1,
+                    "unban_token": "",
+                    "total_executions": 0,
+                    "note": "",
+                    "ban_ip": "",
+                },
+            ],
+        }))
+        .users;
+
+        assert!(users[0].banned);
+        assert_eq!(users[0].identifier_type, UserIdentifierType::HWID);
+        assert_eq!(users[1].identifier, None);
+        assert_eq!(users[1].auth_expire, UserExpiration::Never);
+    }
+
+    // A key in a format this crate doesn't expect mustn't fail the whole page
+    #[test]
+    fn user_keeps_unexpected_ids() {
+        let user = test_user(json!({ "user_key": "key-1", "unban_token": "short" }));
+        assert_eq!(user.user_key.unwrap(), "key-1");
+        assert_eq!(user.unban_token.unwrap(), "short");
+    }
+}
@@ -1,29 +1,42 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
 use serde::Serialize;
 use typed_builder::TypedBuilder;
 
+use crate::models::{DiscordId, ProjectId, UserKey, owned};
+
 /// This will link a Discord ID to an existing key, and optionally override the current linked ID.
 ///
 /// ⚠️ Authenticated ⚠️
 ///
 /// [Reference](https://docs.luarmor.net/#linking-discord-id-to-a-key)
-#[derive(
-    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, TypedBuilder,
-)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct LinkDiscordId<'a> {
     /// The associated project ID that contains the key.
     #[serde(skip)]
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
 
     /// The user key to link to.
-    pub user_key: &'a str,
+    pub user_key: Cow<'a, UserKey>,
     /// The Discord ID you want to associate with the key.
     #[builder(default, setter(strip_option))]
-    pub discord_id: Option<&'a str>,
+    pub discord_id: Option<Cow<'a, DiscordId>>,
     /// If `true`, it will overwrite any current linked Discord ID.
     #[builder(default, setter(strip_option))]
     pub force: Option<bool>,
 }
 #[api_endpoint(method = POST, path = format!("/v3/projects/{}/users/linkdiscord", self.project_id), self_as_body = "application/json")]
 impl Endpoint for LinkDiscordId<'_> {}
+impl LinkDiscordId<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> LinkDiscordId<'static> {
+        LinkDiscordId {
+            project_id: owned(self.project_id),
+            user_key: owned(self.user_key),
+            discord_id: self.discord_id.map(owned),
+            force: self.force,
+        }
+    }
+}
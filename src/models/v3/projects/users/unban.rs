@@ -1,18 +1,31 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
 use typed_builder::TypedBuilder;
 
+use crate::models::{ProjectId, UnbanToken, owned};
+
 /// This will unblacklist a key.
 /// It does not need strict API key authentication, only the `unban_token` which is unique and changes each time a key is blacklisted.
 ///
 /// [Reference](https://docs.luarmor.net/#unblacklisting-a-key)
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct UnblacklistUser<'a> {
     /// The associated project ID that contains the blacklisted key.
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
     /// The unban token, a 32 character random string automatically generated when you blacklist someone.
     /// You can get the blacklist token from [crate::models::v3::projects::users::GetUsers].
-    pub unban_token: &'a str,
+    pub unban_token: Cow<'a, UnbanToken>,
 }
 #[api_endpoint(method = GET, path = format!("/v3/projects/{}/users/unban?unban_token={}", self.project_id, self.unban_token))]
 impl Endpoint for UnblacklistUser<'_> {}
+impl UnblacklistUser<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> UnblacklistUser<'static> {
+        UnblacklistUser {
+            project_id: owned(self.project_id),
+            unban_token: owned(self.unban_token),
+        }
+    }
+}
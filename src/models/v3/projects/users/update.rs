@@ -1,42 +1,161 @@
+use std::borrow::Cow;
+
 use api_builder::{Endpoint, api_endpoint};
 use serde::Serialize;
 use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 
+use crate::models::{DiscordId, Hwid, Patch, ProjectId, UserKey, owned};
+
 /// Update fields of an already existing user.
 ///
 /// ⚠️ Authenticated ⚠️
 ///
 /// [Reference](https://docs.luarmor.net/#updating-an-existing-user)
-#[derive(
-    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, TypedBuilder,
-)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, TypedBuilder)]
 #[builder(field_defaults(setter(into)))]
 pub struct UpdateUser<'a> {
     /// The associated project ID that contains the key.
     #[serde(skip)]
-    pub project_id: &'a str,
+    pub project_id: Cow<'a, ProjectId>,
 
     /// The user key to update.
-    pub user_key: &'a str,
+    pub user_key: Cow<'a, UserKey>,
     /// A unique identifier for the key, usually their HWID.
-    #[builder(default, setter(strip_option))]
-    pub identifier: Option<&'a str>,
+    #[serde(skip_serializing_if = "Patch::is_unchanged")]
+    #[builder(default)]
+    pub identifier: Patch<Cow<'a, Hwid>>,
     /// The unix timestamp of when the key should expire.
     ///
-    /// NOTE: if this is not defined, the key will never expire; read the struct comments for more information.
-    #[serde(with = "time::serde::timestamp::option")]
-    #[builder(default, setter(strip_option))]
-    pub auth_expire: Option<OffsetDateTime>,
+    /// NOTE: clearing this makes the key never expire.
+    #[serde(skip_serializing_if = "Patch::is_unchanged")]
+    #[builder(default)]
+    pub auth_expire: Patch<OffsetDateTime>,
     /// A custom note for the key, which can be used to identify the user and key or provide additional information about them.
-    #[builder(default, setter(strip_option))]
-    pub note: Option<&'a str>,
+    #[serde(skip_serializing_if = "Patch::is_unchanged")]
+    #[builder(default)]
+    pub note: Patch<Cow<'a, str>>,
     /// The Discord ID associated with the key.
     /// However if you set up the Discord bot, the user can manually redeem their key and link it via the "Redeem" button on the panel.
     ///
     /// NOTE: if a Discord ID is not associated with a key, the user cannot use the `/resethwid` discord command.
-    #[builder(default, setter(strip_option))]
-    pub discord_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Patch::is_unchanged")]
+    #[builder(default)]
+    pub discord_id: Patch<Cow<'a, DiscordId>>,
 }
 #[api_endpoint(method = PATCH, path = format!("/v3/projects/{}/users", self.project_id), self_as_body = "application/json")]
 impl Endpoint for UpdateUser<'_> {}
+impl<'a> UpdateUser<'a> {
+    /// Applies `changes` to `user_key` within `project_id`.
+    pub fn new(
+        project_id: impl Into<Cow<'a, ProjectId>>,
+        user_key: impl Into<Cow<'a, UserKey>>,
+        changes: UserChanges<'a>,
+    ) -> Self {
+        Self {
+            project_id: project_id.into(),
+            user_key: user_key.into(),
+            identifier: changes.identifier,
+            auth_expire: changes.auth_expire,
+            note: changes.note,
+            discord_id: changes.discord_id,
+        }
+    }
+}
+impl UpdateUser<'_> {
+    /// Clones any borrowed fields, e.g. to queue the request or move it into another task.
+    pub fn into_owned(self) -> UpdateUser<'static> {
+        UpdateUser {
+            project_id: owned(self.project_id),
+            user_key: owned(self.user_key),
+            identifier: self.identifier.map(owned),
+            auth_expire: self.auth_expire,
+            note: self.note.map(owned),
+            discord_id: self.discord_id.map(owned),
+        }
+    }
+}
+
+/// Changes to a user, without the project or key they apply to.
+///
+/// Used where the key is already known, e.g. [crate::UserHandle::update]. The [Default] changes nothing.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct UserChanges<'a> {
+    /// See [UpdateUser::identifier].
+    pub identifier: Patch<Cow<'a, Hwid>>,
+    /// See [UpdateUser::auth_expire].
+    pub auth_expire: Patch<OffsetDateTime>,
+    /// See [UpdateUser::note].
+    pub note: Patch<Cow<'a, str>>,
+    /// See [UpdateUser::discord_id].
+    pub discord_id: Patch<Cow<'a, DiscordId>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(payload: UpdateUser<'_>) -> String {
+        let (content_type, body) = payload.body().unwrap().unwrap();
+        assert_eq!(content_type, "application/json");
+        String::from_utf8(body).unwrap()
+    }
+
+    fn payload() -> UpdateUser<'static> {
+        UpdateUser::builder()
+            .project_id(ProjectId::new("project").unwrap())
+            .user_key(UserKey::new("key").unwrap())
+            .build()
+    }
+
+    #[test]
+    fn unchanged_fields_are_omitted() {
+        assert_eq!(body(payload()), r#"{"user_key":"key"}"#);
+    }
+
+    #[test]
+    fn set_fields_are_sent() {
+        let payload = UpdateUser {
+            identifier: Patch::Set(Hwid::new("hwid").unwrap().into()),
+            auth_expire: OffsetDateTime::from_unix_timestamp(1700000000)
+                .unwrap()
+                .into(),
+            note: "note".into(),
+            discord_id: Patch::Set(DiscordId::new("1234").unwrap().into()),
+            ..payload()
+        };
+        assert_eq!(
+            body(payload),
+            r#"{"user_key":"key","identifier":"hwid","auth_expire":1700000000,"note":"note","discord_id":"1234"}"#
+        );
+    }
+
+    #[test]
+    fn cleared_fields_are_reset() {
+        let payload = UpdateUser {
+            identifier: Patch::Clear,
+            auth_expire: Patch::Clear,
+            note: Patch::Clear,
+            discord_id: Patch::Clear,
+            ..payload()
+        };
+        assert_eq!(
+            body(payload),
+            r#"{"user_key":"key","identifier":"","auth_expire":-1,"note":"","discord_id":""}"#
+        );
+    }
+
+    #[test]
+    fn fields_are_patched_independently() {
+        let payload = UpdateUser {
+            identifier: Patch::Clear,
+            note: "note".into(),
+            ..payload()
+        };
+        assert_eq!(
+            body(payload),
+            r#"{"user_key":"key","identifier":"","note":"note"}"#
+        );
+    }
+}
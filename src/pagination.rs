@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+
+use api_builder::Client;
+
+use crate::{
+    LuarmorClient, LuarmorError,
+    models::{
+        UserKey,
+        v3::projects::users::{GetUsers, User},
+    },
+};
+
+/// Tracks which page of [GetUsers] to request next.
+///
+/// Shared by [UsersIter] and [LuarmorClient::users_stream].
+#[derive(Clone, Debug)]
+pub(crate) struct UserPages<'a> {
+    filter: GetUsers<'a>,
+    page_size: u32,
+    from: u32,
+    buffer: VecDeque<User>,
+    /// The keys of the last page, to notice a server repeating it.
+    last: Vec<Option<UserKey>>,
+    done: bool,
+}
+impl<'a> UserPages<'a> {
+    /// Starts at `filter.from`, or the first user.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is `0`.
+    pub(crate) fn new(filter: GetUsers<'a>, page_size: u32) -> Self {
+        assert!(page_size > 0, "page_size must be positive");
+        Self {
+            from: filter.from.unwrap_or(0),
+            filter,
+            page_size,
+            buffer: VecDeque::new(),
+            last: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// The next buffered user, if any.
+    pub(crate) fn pop(&mut self) -> Option<User> {
+        self.buffer.pop_front()
+    }
+
+    /// The request for the next page, or `None` once every page has been fetched.
+    pub(crate) fn next_request(&self) -> Option<GetUsers<'a>> {
+        (!self.done).then(|| GetUsers {
+            from: Some(self.from),
+            until: Some(self.from.saturating_add(self.page_size)),
+            ..self.filter.clone()
+        })
+    }
+
+    /// Records the result of the request from [UserPages::next_request].
+    pub(crate) fn push(
+        &mut self,
+        page: Result<Vec<User>, LuarmorError>,
+    ) -> Result<(), LuarmorError> {
+        match page {
+            Ok(users) => {
+                // A server ignoring `from` and `until` would otherwise send the same page forever
+                let keys: Vec<_> = users.iter().map(|x| x.user_key.clone()).collect();
+                if !keys.is_empty() && keys == self.last {
+                    self.done = true;
+                    return Ok(());
+                }
+
+                // A short page means there is nothing left, and a long one that `until` was ignored
+                self.done = users.len() != self.page_size as usize;
+                self.from = self.from.saturating_add(self.page_size);
+                self.last = keys;
+                self.buffer.extend(users);
+                Ok(())
+            }
+            Err(err) => {
+                self.done = true;
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Lazily walks every page of users matching a [GetUsers] filter.
+///
+/// Created by [LuarmorClient::users_iter]. Stops after the first error.
+#[derive(Debug)]
+pub struct UsersIter<'a, C> {
+    client: &'a LuarmorClient<C>,
+    pages: UserPages<'a>,
+}
+impl<'a, C> UsersIter<'a, C> {
+    pub(crate) fn new(client: &'a LuarmorClient<C>, filter: GetUsers<'a>, page_size: u32) -> Self {
+        Self {
+            client,
+            pages: UserPages::new(filter, page_size),
+        }
+    }
+}
+impl<C> Iterator for UsersIter<'_, C>
+where
+    C: Client<Error = LuarmorError>,
+{
+    type Item = Result<User, LuarmorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(user) = self.pages.pop() {
+                return Some(Ok(user));
+            }
+
+            let request = self.pages.next_request()?;
+            if let Err(err) = self.pages.push(self.client.users(request)) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use api_builder::{AsyncClient, Bytes, RestClient, Url, error::APIError};
+    use futures_util::StreamExt;
+    use http::{Request, Response, StatusCode};
+
+    use super::*;
+    use crate::testing::{FakeLuarmor, FakeProject, FakeUser};
+
+    /// Forwards requests to a [FakeLuarmor], keeping their query strings.
+    #[derive(Clone)]
+    struct Pager {
+        fake: FakeLuarmor,
+        queries: Arc<Mutex<Vec<String>>>,
+        /// Answers this request, counting from `0`, with `500`.
+        fail_at: Option<usize>,
+        /// Drops `from` and `until`, like a server which doesn't support them.
+        ignore_paging: bool,
+    }
+    impl Pager {
+        fn new(users: usize) -> Self {
+            let project = (0..users).fold(FakeProject::new("project", "Project"), |project, i| {
+                project.with_user(FakeUser::new(format!("key{i}")))
+            });
+            Self {
+                fake: FakeLuarmor::new("secret").with_project(project),
+                queries: Arc::default(),
+                fail_at: None,
+                ignore_paging: false,
+            }
+        }
+
+        fn respond(&self, mut request: Request<Vec<u8>>) -> Response<Bytes> {
+            let query = request.uri().query().unwrap_or_default().to_string();
+            let mut queries = self.queries.lock().unwrap();
+            queries.push(query.clone());
+            if self.fail_at == Some(queries.len() - 1) {
+                let mut response = Response::new(Bytes::new());
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                return response;
+            }
+
+            if self.ignore_paging {
+                let query: Vec<_> = query
+                    .split('&')
+                    .filter(|x| !x.starts_with("from=") && !x.starts_with("until="))
+                    .collect();
+                let uri = format!("{}?{}", request.uri().path(), query.join("&"));
+                *request.uri_mut() = uri.parse().unwrap();
+            }
+            self.fake.handle(&request)
+        }
+
+        fn queries(&self) -> Vec<String> {
+            self.queries.lock().unwrap().clone()
+        }
+    }
+    impl RestClient for Pager {
+        type Error = LuarmorError;
+
+        fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+            self.fake.rest_endpoint(path)
+        }
+    }
+    impl Client for Pager {
+        fn rest(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond(request))
+        }
+    }
+    impl AsyncClient for Pager {
+        async fn rest_async(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond(request))
+        }
+    }
+
+    /// Walks every page, returning the keys found and the error, if any.
+    fn walk(pager: &Pager, from: Option<u32>, page_size: u32) -> (Vec<String>, bool) {
+        let client = LuarmorClient::new("secret", pager.clone());
+        let project_id = "project".parse().unwrap();
+        let filter = GetUsers {
+            from,
+            ..GetUsers::builder().project_id(&project_id).build()
+        };
+
+        let mut keys = Vec::new();
+        let mut failed = false;
+        for user in client.users_iter(filter, page_size) {
+            match user {
+                Ok(user) => keys.push(user.user_key.unwrap().to_string()),
+                Err(_) => failed = true,
+            }
+        }
+        (keys, failed)
+    }
+
+    fn keys(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("key{i}")).collect()
+    }
+
+    #[test]
+    fn walks_every_page() {
+        let pager = Pager::new(5);
+        assert_eq!(walk(&pager, None, 2), (keys(0..5), false));
+        assert_eq!(
+            pager.queries(),
+            ["from=0&until=2", "from=2&until=4", "from=4&until=6"]
+        );
+    }
+
+    #[test]
+    fn exact_multiple_ends_with_an_empty_page() {
+        let pager = Pager::new(4);
+        assert_eq!(walk(&pager, None, 2), (keys(0..4), false));
+        assert_eq!(pager.queries().len(), 3);
+    }
+
+    #[test]
+    fn starts_at_the_offset() {
+        let pager = Pager::new(5);
+        assert_eq!(walk(&pager, Some(3), 10), (keys(3..5), false));
+        assert_eq!(pager.queries(), ["from=3&until=13"]);
+    }
+
+    #[test]
+    fn stops_after_the_first_error() {
+        let pager = Pager {
+            fail_at: Some(1),
+            ..Pager::new(5)
+        };
+        assert_eq!(walk(&pager, None, 2), (keys(0..2), true));
+        assert_eq!(pager.queries().len(), 2);
+    }
+
+    #[test]
+    fn stops_if_paging_is_ignored() {
+        // Every user at once, more than a page
+        let pager = Pager {
+            ignore_paging: true,
+            ..Pager::new(3)
+        };
+        assert_eq!(walk(&pager, None, 2), (keys(0..3), false));
+        assert_eq!(pager.queries().len(), 1);
+
+        // The same full page every time
+        let pager = Pager {
+            ignore_paging: true,
+            ..Pager::new(2)
+        };
+        assert_eq!(walk(&pager, None, 2), (keys(0..2), false));
+        assert_eq!(pager.queries().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn streams_every_page() {
+        let pager = Pager {
+            fail_at: Some(2),
+            ..Pager::new(5)
+        };
+        let client = LuarmorClient::new("secret", pager.clone());
+        let project_id = "project".parse().unwrap();
+        let filter = GetUsers::builder().project_id(&project_id).build();
+
+        let users: Vec<_> = client.users_stream(filter, 2).collect().await;
+        assert_eq!(users.len(), 5);
+        assert!(users[..4].iter().all(Result::is_ok));
+        assert!(users[4].is_err());
+        assert_eq!(pager.queries().len(), 3);
+    }
+}
@@ -0,0 +1,662 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use typed_builder::TypedBuilder;
+
+use crate::{
+    BulkCreated, BulkFailure, LuarmorError,
+    models::{
+        DiscordId, Hwid, Patch, ProjectId, UnbanToken, UserKey,
+        v3::projects::users::{
+            BlacklistUser, ClaimedKey, CreateUser, KeyOwner, LifetimeKey, NewKey, UpdateUser, User,
+            UserExpiration,
+        },
+    },
+};
+
+/// A user which should have access to the project.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, TypedBuilder)]
+pub struct DesiredUser {
+    /// `None` leaves the current identifier as it is, e.g. so the user can claim the key with their HWID.
+    #[builder(default, setter(into, strip_option))]
+    pub identifier: Option<Hwid>,
+    /// `None` leaves the current Discord ID as it is.
+    #[builder(default, setter(into, strip_option))]
+    pub discord_id: Option<DiscordId>,
+    /// `None` clears the note.
+    #[builder(default, setter(into, strip_option))]
+    pub note: Option<String>,
+    /// `None` means the key never expires.
+    #[builder(default, setter(strip_option))]
+    pub auth_expire: Option<OffsetDateTime>,
+}
+
+/// Which field desired users are matched to existing keys by.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum MatchBy {
+    #[default]
+    DiscordId,
+    Identifier,
+}
+impl MatchBy {
+    /// The field name Luarmor uses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DiscordId => "discord_id",
+            Self::Identifier => "identifier",
+        }
+    }
+
+    fn desired<'a>(&self, user: &'a DesiredUser) -> Option<&'a str> {
+        match self {
+            Self::DiscordId => user.discord_id.as_deref(),
+            Self::Identifier => user.identifier.as_deref(),
+        }
+    }
+
+    fn current<'a>(&self, user: &'a User) -> Option<&'a str> {
+        match self {
+            Self::DiscordId => user.discord_id.as_deref(),
+            Self::Identifier => user.identifier.as_deref(),
+        }
+    }
+}
+
+/// What happens to keys which aren't in the desired set.
+///
+/// Keys without the [MatchBy] field, e.g. unclaimed keys, are never touched.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Unlisted {
+    /// Leave them as they are.
+    #[default]
+    Keep,
+    /// Blacklist them, unless they already are.
+    Blacklist { reason: Option<String> },
+    /// Delete them.
+    Delete,
+}
+
+/// Controls how a [ReconcilePlan] is made.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
+pub struct ReconcileOptions {
+    #[builder(default)]
+    pub match_by: MatchBy,
+    #[builder(default)]
+    pub unlisted: Unlisted,
+    /// If `true`, matched keys which are blacklisted are unblacklisted, otherwise they are left banned.
+    #[builder(default = false)]
+    pub unblacklist: bool,
+    /// How many users to fetch per request, must be positive.
+    #[builder(default = 100)]
+    pub page_size: u32,
+}
+impl Default for ReconcileOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Errors returned while planning a reconcile.
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum ReconcileError {
+    /// The current users could not be fetched.
+    #[error(transparent)]
+    Luarmor(#[from] LuarmorError),
+    /// A desired user is missing the [MatchBy] field.
+    #[error("desired user {index} has no {field} to match by")]
+    Unmatchable { index: usize, field: &'static str },
+    /// More than one desired user has the same [MatchBy] field.
+    #[error("more than one desired user has the {field} `{value}`")]
+    Duplicate { field: &'static str, value: String },
+}
+
+/// A single change within a [ReconcilePlan].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ReconcileAction {
+    Create(CreateUser<'static>),
+    Update(UpdateUser<'static>),
+    /// Lifts the blacklist of a key which should have access, see [ReconcileOptions::unblacklist].
+    Unblacklist {
+        user_key: UserKey,
+        unban_token: UnbanToken,
+    },
+    Blacklist(BlacklistUser<'static>),
+    Delete(UserKey),
+}
+impl fmt::Display for ReconcileAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create(payload) => {
+                let (owner, auth_expire) = match &payload.key {
+                    NewKey::Lifetime(x) => (x.owner.as_ref(), None),
+                    NewKey::Unclaimed(_) => (None, None),
+                    NewKey::Claimed(x) => (Some(&x.owner), Some(x.auth_expire)),
+                };
+                f.write_str("+ create")?;
+                if let Some(x) = owner.and_then(|x| x.discord_id()) {
+                    write!(f, " discord_id={x}")?;
+                }
+                if let Some(x) = owner.and_then(|x| x.identifier()) {
+                    write!(f, " identifier={x}")?;
+                }
+                write!(f, ", expires {}", Expiry(auth_expire))?;
+                if let Some(x) = &payload.note {
+                    write!(f, ", note {x:?}")?;
+                }
+                Ok(())
+            }
+            Self::Update(payload) => {
+                write!(f, "~ update {}:", payload.user_key)?;
+                let changes = [
+                    (
+                        "identifier",
+                        payload.identifier.as_ref().map(|x| x.to_string()),
+                    ),
+                    (
+                        "discord_id",
+                        payload.discord_id.as_ref().map(|x| x.to_string()),
+                    ),
+                    ("note", payload.note.as_ref().map(|x| format!("{x:?}"))),
+                    (
+                        "auth_expire",
+                        payload.auth_expire.map(|x| Expiry(Some(x)).to_string()),
+                    ),
+                ];
+                let changes = changes.iter().filter(|(_, x)| !x.is_unchanged());
+                for (i, (field, change)) in changes.enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    match change {
+                        Patch::Set(x) => write!(f, "{separator}{field} -> {x}")?,
+                        _ => write!(f, "{separator}{field} cleared")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Unblacklist { user_key, .. } => write!(f, "^ unblacklist {user_key}"),
+            Self::Blacklist(payload) => {
+                write!(f, "! blacklist {}", payload.user_key)?;
+                if let Some(x) = &payload.ban_reason {
+                    write!(f, " ({x})")?;
+                }
+                Ok(())
+            }
+            Self::Delete(user_key) => write!(f, "- delete {user_key}"),
+        }
+    }
+}
+
+/// Formats an expiry date, `None` being never.
+struct Expiry(Option<OffsetDateTime>);
+impl fmt::Display for Expiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.map(|x| x.format(&Rfc3339)) {
+            None => f.write_str("never"),
+            Some(Ok(x)) => f.write_str(&x),
+            Some(Err(_)) => f.write_str("invalid date"),
+        }
+    }
+}
+
+/// The changes needed to bring a project in line with the desired users.
+///
+/// Made by [crate::LuarmorClient::plan_reconcile], nothing is changed until it is applied.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ReconcilePlan {
+    pub project_id: ProjectId,
+    pub actions: Vec<ReconcileAction>,
+}
+impl ReconcilePlan {
+    pub(crate) fn new(
+        project_id: &ProjectId,
+        desired: impl IntoIterator<Item = DesiredUser>,
+        current: &[User],
+        options: &ReconcileOptions,
+    ) -> Result<Self, ReconcileError> {
+        let match_by = options.match_by;
+        let field = match_by.name();
+
+        // The first key wins if several share the same field, the rest are unlisted
+        let mut by_field = HashMap::new();
+        for (i, user) in current.iter().enumerate() {
+            if let Some(x) = match_by.current(user) {
+                by_field.entry(x.to_string()).or_insert(i);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut matched = HashSet::new();
+        let mut plan = Self {
+            project_id: project_id.clone(),
+            actions: Vec::new(),
+        };
+        for (index, user) in desired.into_iter().enumerate() {
+            let Some(value) = match_by.desired(&user) else {
+                return Err(ReconcileError::Unmatchable { index, field });
+            };
+            if !seen.insert(value.to_string()) {
+                return Err(ReconcileError::Duplicate {
+                    field,
+                    value: value.to_string(),
+                });
+            }
+
+            match by_field.get(value) {
+                Some(&i) => {
+                    matched.insert(i);
+                    plan.diff(user, &current[i], options);
+                }
+                None => plan.create(index, field, user)?,
+            }
+        }
+
+        for (i, user) in current.iter().enumerate() {
+            if matched.contains(&i) || match_by.current(user).is_none() {
+                continue;
+            }
+            let Some(user_key) = &user.user_key else {
+                continue;
+            };
+
+            match &options.unlisted {
+                Unlisted::Keep => {}
+                Unlisted::Blacklist { .. } if user.banned => {}
+                Unlisted::Blacklist { reason } => {
+                    plan.actions.push(ReconcileAction::Blacklist(BlacklistUser {
+                        project_id: Cow::Owned(project_id.clone()),
+                        user_key: Cow::Owned(user_key.clone()),
+                        ban_reason: reason.clone().map(Cow::Owned),
+                        ban_expire: None,
+                    }))
+                }
+                Unlisted::Delete => plan.actions.push(ReconcileAction::Delete(user_key.clone())),
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Plans a key for `user`, who needs an identifier or Discord ID to own it.
+    fn create(
+        &mut self,
+        index: usize,
+        field: &'static str,
+        user: DesiredUser,
+    ) -> Result<(), ReconcileError> {
+        let owner = match (user.identifier, user.discord_id) {
+            (Some(identifier), Some(discord_id)) => KeyOwner::Both {
+                identifier: identifier.into(),
+                discord_id: discord_id.into(),
+            },
+            (Some(identifier), None) => KeyOwner::Identifier(identifier.into()),
+            (None, Some(discord_id)) => KeyOwner::DiscordId(discord_id.into()),
+            (None, None) => return Err(ReconcileError::Unmatchable { index, field }),
+        };
+        let key = match user.auth_expire {
+            None => LifetimeKey { owner: Some(owner) }.into(),
+            Some(auth_expire) => ClaimedKey { owner, auth_expire }.into(),
+        };
+
+        self.actions.push(ReconcileAction::Create(CreateUser {
+            project_id: Cow::Owned(self.project_id.clone()),
+            key,
+            note: user.note.map(Cow::Owned),
+        }));
+        Ok(())
+    }
+
+    fn diff(&mut self, user: DesiredUser, current: &User, options: &ReconcileOptions) {
+        // Without a key there is nothing to act on
+        let Some(user_key) = &current.user_key else {
+            return;
+        };
+
+        // Keys blacklisted without an unban token stay that way
+        if let (true, true, Some(unban_token)) =
+            (options.unblacklist, current.banned, &current.unban_token)
+        {
+            self.actions.push(ReconcileAction::Unblacklist {
+                user_key: user_key.clone(),
+                unban_token: unban_token.clone(),
+            });
+        }
+
+        let payload = UpdateUser {
+            project_id: Cow::Owned(self.project_id.clone()),
+            user_key: Cow::Owned(user_key.clone()),
+            identifier: match user.identifier {
+                Some(x) if current.identifier.as_ref() != Some(&x) => Patch::Set(Cow::Owned(x)),
+                _ => Patch::Unchanged,
+            },
+            discord_id: match user.discord_id {
+                Some(x) if current.discord_id.as_ref() != Some(&x) => Patch::Set(Cow::Owned(x)),
+                _ => Patch::Unchanged,
+            },
+            note: match (user.note, &current.note) {
+                (None, Some(_)) => Patch::Clear,
+                (Some(x), y) if y.as_ref() != Some(&x) => Patch::Set(Cow::Owned(x)),
+                _ => Patch::Unchanged,
+            },
+            auth_expire: match (user.auth_expire, current.auth_expire) {
+                (None, UserExpiration::Specified(_)) => Patch::Clear,
+                (Some(x), UserExpiration::Never) => Patch::Set(x),
+                (Some(x), UserExpiration::Specified(y))
+                    if x.unix_timestamp() != y.unix_timestamp() =>
+                {
+                    Patch::Set(x)
+                }
+                _ => Patch::Unchanged,
+            },
+        };
+
+        let changed = !payload.identifier.is_unchanged()
+            || !payload.discord_id.is_unchanged()
+            || !payload.note.is_unchanged()
+            || !payload.auth_expire.is_unchanged();
+        if changed {
+            self.actions.push(ReconcileAction::Update(payload));
+        }
+    }
+
+    /// If the project already matches the desired users.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+impl fmt::Display for ReconcilePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes to project {}", self.project_id);
+        }
+
+        write!(
+            f,
+            "{} change(s) to project {}:",
+            self.actions.len(),
+            self.project_id
+        )?;
+        for action in &self.actions {
+            write!(f, "\n  {action}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of [crate::LuarmorClient::apply_reconcile], indexed by [ReconcilePlan::actions].
+#[derive(Clone, Debug, Default)]
+pub struct ReconcileReport {
+    /// Every action which succeeded.
+    pub applied: Vec<usize>,
+    /// The keys made by [ReconcileAction::Create].
+    pub created: Vec<BulkCreated>,
+    pub failed: Vec<BulkFailure>,
+}
+impl ReconcileReport {
+    pub(crate) fn push(&mut self, index: usize, result: Result<Option<UserKey>, LuarmorError>) {
+        match result {
+            Ok(user_key) => {
+                self.applied.push(index);
+                if let Some(user_key) = user_key {
+                    self.created.push(BulkCreated { index, user_key });
+                }
+            }
+            Err(error) => self.failed.push(BulkFailure { index, error }),
+        }
+    }
+
+    /// If every action succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::v3::projects::users::test_user;
+
+    fn banned_user() -> User {
+        test_user(json!({
+            "discord_id": "1030578127133286410",
+            "status": "banned",
+            "banned": 1,
+            "ban_reason": "leaking",
+            "unban_token": "a".repeat(32),
+        }))
+    }
+
+    fn user(discord_id: &str, user_key: &str) -> User {
+        test_user(json!({ "discord_id": discord_id, "user_key": user_key }))
+    }
+
+    fn desired(discord_id: &str) -> DesiredUser {
+        DesiredUser::builder()
+            .discord_id(DiscordId::new(discord_id).unwrap())
+            .build()
+    }
+
+    fn at(timestamp: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
+    }
+
+    /// The planned actions as displayed.
+    fn changes(
+        desired: impl IntoIterator<Item = DesiredUser>,
+        current: &[User],
+        options: &ReconcileOptions,
+    ) -> Result<Vec<String>, ReconcileError> {
+        let plan = ReconcilePlan::new(&"project".parse().unwrap(), desired, current, options)?;
+        Ok(plan.actions.iter().map(|x| x.to_string()).collect())
+    }
+
+    fn plan(options: &ReconcileOptions) -> ReconcilePlan {
+        let desired = DesiredUser::builder()
+            .discord_id(DiscordId::new("1030578127133286410").unwrap())
+            .build();
+        ReconcilePlan::new(
+            &"project".parse().unwrap(),
+            [desired],
+            &[banned_user()],
+            options,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn banned_keys_are_kept_banned() {
+        assert!(plan(&ReconcileOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn banned_keys_are_unblacklisted_if_asked() {
+        let options = ReconcileOptions::builder().unblacklist(true).build();
+        assert_eq!(
+            plan(&options).actions,
+            [ReconcileAction::Unblacklist {
+                user_key: "key1".parse().unwrap(),
+                unban_token: "a".repeat(32).parse().unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn creates_missing_users() {
+        let claimed = DesiredUser::builder()
+            .discord_id(DiscordId::new("2").unwrap())
+            .identifier(Hwid::new("hwid").unwrap())
+            .auth_expire(at(1800000000))
+            .note("trial")
+            .build();
+        let changes = changes([desired("1"), claimed], &[], &ReconcileOptions::default());
+        assert_eq!(
+            changes.unwrap(),
+            [
+                "+ create discord_id=1, expires never",
+                "+ create discord_id=2 identifier=hwid, expires 2027-01-15T08:00:00Z, note \"trial\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn creates_lifetime_and_claimed_keys() {
+        let lifetime = desired("1");
+        let claimed = DesiredUser {
+            auth_expire: Some(at(1800000000)),
+            ..desired("2")
+        };
+        let plan = ReconcilePlan::new(
+            &"project".parse().unwrap(),
+            [lifetime, claimed],
+            &[],
+            &ReconcileOptions::default(),
+        )
+        .unwrap();
+        let keys: Vec<_> = plan
+            .actions
+            .iter()
+            .map(|x| match x {
+                ReconcileAction::Create(x) => &x.key,
+                x => panic!("expected a create, got {x}"),
+            })
+            .collect();
+        assert!(matches!(keys[0], NewKey::Lifetime(x) if x.owner.is_some()));
+        assert!(matches!(keys[1], NewKey::Claimed(x) if x.auth_expire == at(1800000000)));
+    }
+
+    #[test]
+    fn updates_only_changed_fields() {
+        let current = [
+            test_user(json!({ "discord_id": "1", "user_key": "key1", "note": "old" })),
+            test_user(json!({ "discord_id": "2", "user_key": "key2", "auth_expire": 1800000000 })),
+            test_user(json!({ "discord_id": "3", "user_key": "key3", "note": "same" })),
+        ];
+        let desired = [
+            DesiredUser {
+                identifier: Some(Hwid::new("hwid").unwrap()),
+                note: Some("new".to_string()),
+                ..desired("1")
+            },
+            desired("2"),
+            DesiredUser {
+                note: Some("same".to_string()),
+                ..desired("3")
+            },
+        ];
+        let changes = changes(desired, &current, &ReconcileOptions::default());
+        assert_eq!(
+            changes.unwrap(),
+            [
+                "~ update key1: identifier -> hwid, note -> \"new\"",
+                "~ update key2: auth_expire cleared",
+            ]
+        );
+    }
+
+    #[test]
+    fn clears_notes_and_sets_expiry() {
+        let current = [test_user(json!({ "discord_id": "1", "note": "old" }))];
+        let desired = DesiredUser {
+            auth_expire: Some(at(1800000000)),
+            ..desired("1")
+        };
+        let changes = changes([desired], &current, &ReconcileOptions::default());
+        assert_eq!(
+            changes.unwrap(),
+            ["~ update key1: note cleared, auth_expire -> 2027-01-15T08:00:00Z"]
+        );
+    }
+
+    #[test]
+    fn handles_unlisted_keys() {
+        let current = [
+            user("1", "key1"),
+            user("2", "key2"),
+            test_user(json!({ "discord_id": "3", "user_key": "key3", "banned": 1 })),
+            // Unclaimed, so never touched
+            user("", "key4"),
+        ];
+        let changes = |unlisted| {
+            let options = ReconcileOptions::builder().unlisted(unlisted).build();
+            changes([desired("1")], &current, &options).unwrap()
+        };
+
+        assert!(changes(Unlisted::Keep).is_empty());
+        assert_eq!(
+            changes(Unlisted::Blacklist {
+                reason: Some("expired".to_string())
+            }),
+            ["! blacklist key2 (expired)"]
+        );
+        assert_eq!(
+            changes(Unlisted::Delete),
+            ["- delete key2", "- delete key3"]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_users() {
+        let error = changes(
+            [desired("1"), desired("1")],
+            &[],
+            &ReconcileOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ReconcileError::Duplicate { field: "discord_id", value } if value == "1"
+        ));
+    }
+
+    #[test]
+    fn rejects_unmatchable_users() {
+        let anonymous = DesiredUser {
+            discord_id: None,
+            identifier: Some(Hwid::new("hwid").unwrap()),
+            ..desired("1")
+        };
+        let error =
+            changes([desired("1"), anonymous], &[], &ReconcileOptions::default()).unwrap_err();
+        assert!(matches!(
+            error,
+            ReconcileError::Unmatchable {
+                index: 1,
+                field: "discord_id"
+            }
+        ));
+    }
+
+    #[test]
+    fn matches_by_identifier() {
+        let current = [
+            test_user(json!({ "identifier": "hwid", "discord_id": "1", "user_key": "key1" })),
+            test_user(json!({ "identifier": "other", "user_key": "key2" })),
+        ];
+        let desired = DesiredUser::builder()
+            .identifier(Hwid::new("hwid").unwrap())
+            .discord_id(DiscordId::new("2").unwrap())
+            .build();
+        let options = ReconcileOptions::builder()
+            .match_by(MatchBy::Identifier)
+            .unlisted(Unlisted::Delete)
+            .build();
+        assert_eq!(
+            changes([desired], &current, &options).unwrap(),
+            ["~ update key1: discord_id -> 2", "- delete key2"]
+        );
+
+        // Keys are matched by Discord ID by default, so the same user is new
+        let desired = DesiredUser::builder()
+            .identifier(Hwid::new("hwid").unwrap())
+            .discord_id(DiscordId::new("2").unwrap())
+            .build();
+        assert_eq!(
+            changes([desired], &current, &ReconcileOptions::default()).unwrap(),
+            ["+ create discord_id=2 identifier=hwid, expires never"]
+        );
+    }
+}
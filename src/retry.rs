@@ -0,0 +1,296 @@
+use std::{thread, time::Duration};
+
+use api_builder::{
+    AsyncClient, Bytes, Client, RestClient, Url,
+    error::{APIError, APIErrorKind},
+};
+use http::{Request, Response, StatusCode, header::RETRY_AFTER};
+use typed_builder::TypedBuilder;
+
+use crate::{delay, route::Route, util::random_u64};
+
+/// Controls how [Retry] retries transient failures.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
+pub struct RetryPolicy {
+    /// The maximum number of retries, on top of the initial attempt.
+    #[builder(default = 3)]
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for each subsequent retry.
+    #[builder(default = Duration::from_millis(250))]
+    pub base_delay: Duration,
+    /// The upper bound of the exponential backoff, and of any `Retry-After` header sent by the server.
+    #[builder(default = Duration::from_secs(30))]
+    pub max_delay: Duration,
+    /// If `true`, requests which are not idempotent (e.g. [crate::models::v3::projects::users::CreateUser]) are retried too.
+    ///
+    /// Use with caution, a request which timed out may still have been applied.
+    #[builder(default = false)]
+    pub retry_non_idempotent: bool,
+}
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+impl RetryPolicy {
+    /// Whether `request` may be retried at all.
+    fn applies_to<B>(&self, request: &Request<B>) -> bool {
+        self.retry_non_idempotent || Route::from_request(request).is_some_and(|x| x.is_idempotent())
+    }
+
+    /// The jittered exponential backoff for the given attempt, starting at `0`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        ceiling.mul_f64(random_u64() as f64 / u64::MAX as f64)
+    }
+
+    /// How long to wait before retrying, or `None` if `result` should be returned as is.
+    fn retry_delay<E>(
+        &self,
+        attempt: u32,
+        result: &Result<Response<Bytes>, APIError<E>>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+
+        let backoff = self.backoff(attempt);
+        match result {
+            Ok(response) if is_transient(response.status()) => Some(
+                retry_after(response)
+                    .map(|x| x.min(self.max_delay).max(backoff))
+                    .unwrap_or(backoff),
+            ),
+            Err(err) if matches!(err.kind(), APIErrorKind::HttpClient(_)) => Some(backoff),
+            _ => None,
+        }
+    }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Parses the `Retry-After` header, only the delay-seconds form is supported.
+fn retry_after(response: &Response<Bytes>) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+fn clone_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+/// Wraps a client, retrying transient failures (`429`, `5xx` and transport errors) with jittered exponential backoff.
+///
+/// Only idempotent endpoints are retried, unless [RetryPolicy::retry_non_idempotent] is set.
+#[derive(Clone, Debug)]
+pub struct Retry<C> {
+    client: C,
+    policy: RetryPolicy,
+}
+impl<C> Retry<C> {
+    /// Creates an instance.
+    pub fn new(client: C, policy: RetryPolicy) -> Self {
+        Self { client, policy }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+impl<C> RestClient for Retry<C>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        self.client.rest_endpoint(path)
+    }
+}
+impl<C> Client for Retry<C>
+where
+    C: Client,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        if !self.policy.applies_to(&request) {
+            return self.client.rest(request);
+        }
+
+        let mut attempt = 0;
+        loop {
+            let result = self.client.rest(clone_request(&request));
+            match self.policy.retry_delay(attempt, &result) {
+                Some(delay) => thread::sleep(delay),
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+}
+impl<C> AsyncClient for Retry<C>
+where
+    C: AsyncClient + Sync,
+    C::Error: Send,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        if !self.policy.applies_to(&request) {
+            return self.client.rest_async(request).await;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let delay = {
+                let result = self.client.rest_async(clone_request(&request)).await;
+                match self.policy.retry_delay(attempt, &result) {
+                    Some(delay) => delay,
+                    None => return result,
+                }
+            };
+            delay::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+    use crate::LuarmorError;
+
+    /// Answers every request with `503`, counting them.
+    #[derive(Clone, Default)]
+    struct Unavailable(Arc<AtomicU32>);
+    impl Unavailable {
+        fn respond(&self) -> Response<Bytes> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            unavailable(None)
+        }
+
+        fn count(&self) -> u32 {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+    impl RestClient for Unavailable {
+        type Error = LuarmorError;
+
+        fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+            Ok(Url::parse("https://api.luarmor.net")?.join(path)?)
+        }
+    }
+    impl Client for Unavailable {
+        fn rest(&self, _: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond())
+        }
+    }
+    impl AsyncClient for Unavailable {
+        async fn rest_async(
+            &self,
+            _: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            Ok(self.respond())
+        }
+    }
+
+    fn unavailable(retry_after: Option<&str>) -> Response<Bytes> {
+        let mut response = Response::new(Bytes::new());
+        *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        if let Some(x) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, x.parse().unwrap());
+        }
+        response
+    }
+
+    /// Retries twice, without waiting.
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::ZERO,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn status_request() -> Request<Vec<u8>> {
+        Request::get("https://api.luarmor.net/status")
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    fn create_user_request() -> Request<Vec<u8>> {
+        Request::post("https://api.luarmor.net/v3/projects/abc/users")
+            .body(b"{}".to_vec())
+            .unwrap()
+    }
+
+    #[test]
+    fn honours_retry_after_up_to_max_delay() {
+        let policy = RetryPolicy {
+            max_delay: Duration::from_secs(30),
+            ..policy()
+        };
+        let delay = |x| policy.retry_delay::<LuarmorError>(0, &Ok(unavailable(Some(x))));
+
+        assert_eq!(delay("2"), Some(Duration::from_secs(2)));
+        assert_eq!(delay("86400"), Some(Duration::from_secs(30)));
+        assert_eq!(delay("soon"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn stops_after_max_retries() {
+        let inner = Unavailable::default();
+        let client = Retry::new(inner.clone(), policy());
+
+        let response = client.rest(status_request()).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(inner.count(), 3);
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_retries_async() {
+        let inner = Unavailable::default();
+        let client = Retry::new(inner.clone(), policy());
+
+        let response = client.rest_async(status_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(inner.count(), 3);
+    }
+
+    #[test]
+    fn non_idempotent_requests_are_sent_once() {
+        let inner = Unavailable::default();
+        let client = Retry::new(inner.clone(), policy());
+        client.rest(create_user_request()).unwrap();
+        assert_eq!(inner.count(), 1);
+
+        let inner = Unavailable::default();
+        let client = Retry::new(
+            inner.clone(),
+            RetryPolicy {
+                retry_non_idempotent: true,
+                ..policy()
+            },
+        );
+        client.rest(create_user_request()).unwrap();
+        assert_eq!(inner.count(), 3);
+    }
+}
@@ -0,0 +1,98 @@
+use http::{Method, Request};
+
+/// The Luarmor endpoint a request targets, derived from its method and path.
+///
+/// Used by the client layers to decide how a request should be treated, e.g. whether it is safe to retry.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Route {
+    /// [crate::models::status::ApiStatus]
+    Status,
+    /// [crate::models::v3::keys::ApiKeyDetails]
+    KeyDetails,
+    /// [crate::models::v3::keys::ApiKeyStats]
+    KeyStats,
+    /// [crate::models::v3::projects::users::GetUsers]
+    ///
+    /// `filtered` is `true` if any of `user_key`, `discord_id`, `identifier` or `search` were given.
+    GetUsers { filtered: bool },
+    /// [crate::models::v3::projects::users::CreateUser]
+    CreateUser,
+    /// [crate::models::v3::projects::users::UpdateUser]
+    UpdateUser,
+    /// [crate::models::v3::projects::users::DeleteUser]
+    DeleteUser,
+    /// [crate::models::v3::projects::users::ResetHwid]
+    ResetHwid,
+    /// [crate::models::v3::projects::users::LinkDiscordId]
+    LinkDiscord,
+    /// [crate::models::v3::projects::users::BlacklistUser]
+    BlacklistUser,
+    /// [crate::models::v3::projects::users::UnblacklistUser]
+    UnblacklistUser,
+    /// [crate::models::v3::projects::scripts::UpdateScript]
+    UpdateScript,
+}
+impl Route {
+    /// Classifies a request, returning `None` if it does not target a known endpoint.
+    pub fn from_request<B>(request: &Request<B>) -> Option<Self> {
+        let uri = request.uri();
+        let segments: Vec<&str> = uri.path().split('/').filter(|x| !x.is_empty()).collect();
+        let method = request.method();
+
+        Some(match (method, segments.as_slice()) {
+            (&Method::GET, [.., "status"]) => Self::Status,
+            (&Method::GET, [.., "v3", "keys", _, "details"]) => Self::KeyDetails,
+            (&Method::GET, [.., "v3", "keys", _, "stats"]) => Self::KeyStats,
+            (&Method::GET, [.., "v3", "projects", _, "users"]) => Self::GetUsers {
+                filtered: uri.query().is_some_and(|query| {
+                    query.split('&').any(|pair| {
+                        matches!(
+                            pair.split('=').next(),
+                            Some("user_key" | "discord_id" | "identifier" | "search")
+                        )
+                    })
+                }),
+            },
+            (&Method::POST, [.., "v3", "projects", _, "users"]) => Self::CreateUser,
+            (&Method::PATCH, [.., "v3", "projects", _, "users"]) => Self::UpdateUser,
+            (&Method::DELETE, [.., "v3", "projects", _, "users"]) => Self::DeleteUser,
+            (&Method::POST, [.., "v3", "projects", _, "users", "resethwid"]) => Self::ResetHwid,
+            (&Method::POST, [.., "v3", "projects", _, "users", "linkdiscord"]) => Self::LinkDiscord,
+            (&Method::POST, [.., "v3", "projects", _, "users", "blacklist"]) => Self::BlacklistUser,
+            (&Method::GET, [.., "v3", "projects", _, "users", "unban"]) => Self::UnblacklistUser,
+            (&Method::PUT, [.., "v3", "projects", _, "scripts", _]) => Self::UpdateScript,
+            _ => return None,
+        })
+    }
+
+    /// A stable name for the endpoint, e.g. `delete_user`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::KeyDetails => "key_details",
+            Self::KeyStats => "key_stats",
+            Self::GetUsers { .. } => "get_users",
+            Self::CreateUser => "create_user",
+            Self::UpdateUser => "update_user",
+            Self::DeleteUser => "delete_user",
+            Self::ResetHwid => "reset_hwid",
+            Self::LinkDiscord => "link_discord",
+            Self::BlacklistUser => "blacklist_user",
+            Self::UnblacklistUser => "unblacklist_user",
+            Self::UpdateScript => "update_script",
+        }
+    }
+
+    /// Whether sending the request more than once has the same effect as sending it once.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Self::Status | Self::KeyDetails | Self::KeyStats | Self::GetUsers { .. }
+        )
+    }
+
+    /// Whether the request changes any state on Luarmor.
+    pub fn is_mutating(&self) -> bool {
+        !self.is_idempotent()
+    }
+}
@@ -0,0 +1,54 @@
+use std::{borrow::Cow, fmt};
+
+use zeroize::Zeroize;
+
+/// What secrets are replaced with when formatted.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// A Luarmor API key.
+///
+/// The key is wiped from memory once dropped, and prints as `[REDACTED]`.
+/// It can only be read via [ApiKey::expose].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct ApiKey(String);
+impl ApiKey {
+    /// Creates an instance.
+    pub fn new(api_key: String) -> Self {
+        Self(api_key)
+    }
+
+    /// Exposes the raw key, only call this when the key must actually be sent.
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+}
+impl From<String> for ApiKey {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+impl From<&str> for ApiKey {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_string())
+    }
+}
+impl<'a> From<&'a ApiKey> for Cow<'a, ApiKey> {
+    fn from(value: &'a ApiKey) -> Self {
+        Cow::Borrowed(value)
+    }
+}
+impl From<ApiKey> for Cow<'_, ApiKey> {
+    fn from(value: ApiKey) -> Self {
+        Cow::Owned(value)
+    }
+}
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+impl Drop for ApiKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
@@ -0,0 +1,247 @@
+use time::{Duration, OffsetDateTime};
+use typed_builder::TypedBuilder;
+
+use crate::{
+    LuarmorError,
+    models::{
+        UserKey,
+        v3::projects::users::{User, UserExpiration},
+    },
+};
+
+/// Where a sweep gets the current time from.
+///
+/// [OffsetDateTime] is a clock frozen at that time, e.g. for tests or replaying a past sweep.
+pub trait Clock {
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The system clock, in UTC.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+impl Clock for OffsetDateTime {
+    fn now(&self) -> OffsetDateTime {
+        *self
+    }
+}
+
+/// What happens to expired keys.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum SweepAction {
+    #[default]
+    Delete,
+    /// Blacklist them instead, skipping any which already are.
+    Blacklist { reason: Option<String> },
+}
+
+/// Controls which keys a sweep removes.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, TypedBuilder)]
+pub struct SweepOptions {
+    /// How long a key must have been expired for before it's swept.
+    ///
+    /// A negative grace counts as zero, so keys are never swept before they expire.
+    #[builder(default = Duration::ZERO)]
+    pub grace: Duration,
+    #[builder(default)]
+    pub action: SweepAction,
+    /// If `true`, keys are only reported and nothing is changed.
+    #[builder(default = false)]
+    pub dry_run: bool,
+    /// The most keys to sweep in a single run, the longest expired go first.
+    #[builder(default, setter(strip_option))]
+    pub limit: Option<usize>,
+    /// How many users to fetch per request, must be positive.
+    #[builder(default = 100)]
+    pub page_size: u32,
+}
+impl Default for SweepOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// An expired key found by a sweep.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ExpiredKey {
+    pub user_key: UserKey,
+    pub auth_expire: OffsetDateTime,
+}
+
+/// A key which could not be swept.
+#[derive(Clone, Debug)]
+pub struct SweepFailure {
+    pub key: ExpiredKey,
+    pub error: LuarmorError,
+}
+
+/// The outcome of [crate::LuarmorClient::sweep_expired].
+#[derive(Clone, Debug, Default)]
+pub struct SweepReport {
+    /// The keys which were swept, or would have been for a dry run.
+    pub swept: Vec<ExpiredKey>,
+    pub failed: Vec<SweepFailure>,
+    /// How many expired keys were left for a later run because of [SweepOptions::limit].
+    pub deferred: usize,
+}
+impl SweepReport {
+    /// If every key was swept, and none were deferred.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.deferred == 0
+    }
+
+    pub(crate) fn push(&mut self, key: ExpiredKey, result: Result<(), LuarmorError>) {
+        match result {
+            Ok(()) => self.swept.push(key),
+            Err(error) => self.failed.push(SweepFailure { key, error }),
+        }
+    }
+}
+
+/// Finds the keys to sweep, returning them with how many were deferred.
+pub(crate) fn expired(
+    users: Vec<User>,
+    now: OffsetDateTime,
+    options: &SweepOptions,
+) -> (Vec<ExpiredKey>, usize) {
+    let blacklisting = matches!(options.action, SweepAction::Blacklist { .. });
+    let grace = options.grace.max(Duration::ZERO);
+
+    let mut keys: Vec<_> = users
+        .into_iter()
+        .filter(|x| !(blacklisting && x.banned))
+        .filter_map(|x| match (x.user_key, x.auth_expire) {
+            // A grace period past the end of time never ends
+            (Some(user_key), UserExpiration::Specified(auth_expire))
+                if auth_expire.checked_add(grace).is_some_and(|x| x < now) =>
+            {
+                Some(ExpiredKey {
+                    user_key,
+                    auth_expire,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    keys.sort_by_key(|x| x.auth_expire);
+
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let deferred = keys.len().saturating_sub(limit);
+    keys.truncate(limit);
+    (keys, deferred)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        models::v3::projects::users::test_user,
+        testing::{FakeLuarmor, FakeProject, FakeUser},
+    };
+
+    fn user(user_key: &str, auth_expire: i64) -> User {
+        test_user(json!({ "user_key": user_key, "auth_expire": auth_expire }))
+    }
+
+    const NOW: i64 = 1700000000;
+
+    /// The keys swept at [NOW], and how many were deferred.
+    fn sweep(users: Vec<User>, options: &SweepOptions) -> (Vec<String>, usize) {
+        let now = OffsetDateTime::from_unix_timestamp(NOW).unwrap();
+        let (keys, deferred) = expired(users, now, options);
+        let keys = keys.into_iter().map(|x| x.user_key.to_string()).collect();
+        (keys, deferred)
+    }
+
+    fn keys(users: Vec<User>, options: &SweepOptions) -> Vec<String> {
+        sweep(users, options).0
+    }
+
+    #[test]
+    fn sweeps_keys_past_their_grace() {
+        let users = vec![
+            user("lifetime", -1),
+            user("expired", 1600000000),
+            user("grace", 1699999000),
+            user("active", 1800000000),
+        ];
+        let options = SweepOptions::builder().grace(Duration::hours(1)).build();
+        assert_eq!(keys(users, &options), ["expired"]);
+    }
+
+    #[test]
+    fn huge_grace_never_expires() {
+        let options = SweepOptions::builder().grace(Duration::MAX).build();
+        assert!(keys(vec![user("expired", 1600000000)], &options).is_empty());
+    }
+
+    #[test]
+    fn negative_grace_counts_as_zero() {
+        let users = vec![user("expired", NOW - 60), user("active", NOW + 3600)];
+        let options = SweepOptions::builder().grace(Duration::hours(-2)).build();
+        assert_eq!(keys(users, &options), ["expired"]);
+    }
+
+    #[test]
+    fn limit_defers_the_newest() {
+        let users = vec![
+            user("newest", NOW - 10),
+            user("oldest", NOW - 1000),
+            user("middle", NOW - 100),
+        ];
+        let options = SweepOptions::builder().limit(2).build();
+        assert_eq!(
+            sweep(users, &options),
+            (vec!["oldest".into(), "middle".into()], 1)
+        );
+    }
+
+    #[test]
+    fn blacklisting_skips_banned_keys() {
+        let banned =
+            test_user(json!({ "user_key": "banned", "auth_expire": NOW - 10, "banned": 1 }));
+        let users = vec![user("expired", NOW - 10), banned];
+
+        let options = SweepOptions::builder()
+            .action(SweepAction::Blacklist { reason: None })
+            .build();
+        assert_eq!(keys(users.clone(), &options), ["expired"]);
+        assert_eq!(keys(users, &SweepOptions::default()), ["expired", "banned"]);
+    }
+
+    #[test]
+    fn dry_runs_change_nothing() {
+        let mut expired = FakeUser::new("expired");
+        expired.auth_expire = NOW - 10;
+        let fake = FakeLuarmor::new("secret").with_project(
+            FakeProject::new("project", "Project")
+                .with_user(expired)
+                .with_user(FakeUser::new("lifetime")),
+        );
+        let client = fake.client();
+        let project_id = "project".parse().unwrap();
+        let project = client.project(&project_id);
+        let clock = OffsetDateTime::from_unix_timestamp(NOW).unwrap();
+
+        let options = SweepOptions::builder().dry_run(true).build();
+        let report = project.sweep_expired(&options, &clock).unwrap();
+        assert_eq!(report.swept[0].user_key.as_str(), "expired");
+        assert!(report.is_complete());
+        assert_eq!(fake.state().project("project").unwrap().users.len(), 2);
+
+        let report = project
+            .sweep_expired(&SweepOptions::default(), &clock)
+            .unwrap();
+        assert_eq!(report.swept.len(), 1);
+        let state = fake.state();
+        let users = &state.project("project").unwrap().users;
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].user_key, "lifetime");
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use api_builder::{AsyncClient, Bytes, Client, RestClient, Url, error::APIError};
+use http::{Request, Response};
+
+use crate::{LuarmorClient, LuarmorError};
+
+mod routes;
+import!(seed, state);
+
+/// An in-memory fake of the Luarmor API, implementing [Client] and [AsyncClient].
+///
+/// Every clone shares the same state.
+#[derive(Clone, Debug)]
+pub struct FakeLuarmor {
+    api_key: String,
+    state: Arc<Mutex<FakeState>>,
+}
+impl FakeLuarmor {
+    /// Creates an instance without any projects, which only accepts `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_state(api_key, FakeState::default())
+    }
+
+    /// Creates an instance seeded with `state`, which only accepts `api_key`.
+    pub fn with_state(api_key: impl Into<String>, state: FakeState) -> Self {
+        Self {
+            api_key: api_key.into(),
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Adds a project.
+    pub fn with_project(self, project: FakeProject) -> Self {
+        self.state().projects.push(project);
+        self
+    }
+
+    /// Locks and returns the state, e.g. to assert on it or modify it.
+    pub fn state(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Creates a [LuarmorClient] which sends requests to this instance with the correct API key.
+    pub fn client(&self) -> LuarmorClient<Self> {
+        LuarmorClient::new(self.api_key.as_str(), self.clone())
+    }
+
+    /// Handles `request`, returning the response Luarmor would.
+    pub fn handle(&self, request: &Request<Vec<u8>>) -> Response<Bytes> {
+        routes::handle(&mut self.state(), &self.api_key, request)
+    }
+}
+impl RestClient for FakeLuarmor {
+    type Error = LuarmorError;
+
+    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>> {
+        Ok(Url::parse("https://api.luarmor.net")?.join(path)?)
+    }
+}
+impl Client for FakeLuarmor {
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        Ok(self.handle(&request))
+    }
+}
+impl AsyncClient for FakeLuarmor {
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        Ok(self.handle(&request))
+    }
+}
@@ -0,0 +1,908 @@
+use std::collections::HashMap;
+
+use api_builder::{Bytes, Url};
+use http::{
+    Method, Request, Response, StatusCode,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{
+    models::{LuarmorMessage, MessageKind},
+    testing::{FakeProject, FakeState, FakeUser},
+    util::random_token,
+};
+
+type Reply = (StatusCode, Value);
+
+/// A successful reply, `data` must be an object.
+fn reply(message: LuarmorMessage, mut data: Value) -> Reply {
+    data["success"] = true.into();
+    data["message"] = message.as_str().into();
+    (StatusCode::OK, data)
+}
+
+fn reject(message: LuarmorMessage) -> Reply {
+    let status = match message.kind() {
+        MessageKind::NotFound => StatusCode::NOT_FOUND,
+        MessageKind::AuthFailure => StatusCode::FORBIDDEN,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (
+        status,
+        json!({ "success": false, "message": message.as_str() }),
+    )
+}
+
+fn other(message: &str) -> LuarmorMessage {
+    LuarmorMessage::Other(message.to_string())
+}
+
+fn is_valid_discord_id(discord_id: &str) -> bool {
+    !discord_id.is_empty() && discord_id.bytes().all(|x| x.is_ascii_digit())
+}
+
+fn parse_body<T: DeserializeOwned>(request: &Request<Vec<u8>>) -> Result<T, Reply> {
+    serde_json::from_slice(request.body()).map_err(|_| reject(other("Invalid body")))
+}
+
+/// Handles a request exactly as Luarmor would.
+pub(crate) fn handle(
+    state: &mut FakeState,
+    api_key: &str,
+    request: &Request<Vec<u8>>,
+) -> Response<Bytes> {
+    let (status, body) = route(state, api_key, request).unwrap_or_else(|x| x);
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Bytes::from(body.to_string()))
+        .expect("valid response")
+}
+
+fn route(state: &mut FakeState, api_key: &str, request: &Request<Vec<u8>>) -> Result<Reply, Reply> {
+    // The URI may only be a path when coming from a server
+    let url = Url::parse("http://localhost")
+        .and_then(|x| x.join(&request.uri().to_string()))
+        .map_err(|_| reject(LuarmorMessage::NothingToSee))?;
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|x| x.filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default();
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .is_some_and(|x| x.as_bytes() == api_key.as_bytes());
+
+    match (request.method(), segments.as_slice()) {
+        (&Method::GET, ["status"]) => Ok(status()),
+        (&Method::GET, ["v3", "keys", key, endpoint]) => {
+            if *key != api_key {
+                return Err(reject(LuarmorMessage::InvalidAPIKey));
+            }
+
+            match *endpoint {
+                "details" => Ok(details(state)),
+                "stats" => Ok(stats(
+                    state,
+                    query.get("noUsers").is_some_and(|x| x == "true"),
+                )),
+                _ => Err(reject(LuarmorMessage::NothingToSee)),
+            }
+        }
+        (&Method::GET, ["v3", "projects", project_id, "users", "unban"]) => {
+            let project = project(state, project_id)?;
+            unban(project, query.get("unban_token").map(String::as_str))
+        }
+        (method, ["v3", "projects", project_id, rest @ ..]) => {
+            if !authorized {
+                return Err(reject(LuarmorMessage::IncorrectAPIKey));
+            }
+
+            let now = state.now();
+            let project = project(state, project_id)?;
+            match (method, rest) {
+                (&Method::GET, ["users"]) => Ok(users(project, &query)),
+                (&Method::POST, ["users"]) => create_user(project, now, parse_body(request)?),
+                (&Method::PATCH, ["users"]) => update_user(project, parse_body(request)?),
+                (&Method::DELETE, ["users"]) => {
+                    delete_user(project, query.get("user_key").map(String::as_str))
+                }
+                (&Method::POST, ["users", "resethwid"]) => {
+                    reset_hwid(project, now, parse_body(request)?)
+                }
+                (&Method::POST, ["users", "linkdiscord"]) => {
+                    link_discord(project, parse_body(request)?)
+                }
+                (&Method::POST, ["users", "blacklist"]) => blacklist(project, parse_body(request)?),
+                (&Method::PUT, ["scripts", script_id]) => {
+                    update_script(project, script_id, parse_body(request)?)
+                }
+                _ => Err(reject(LuarmorMessage::NothingToSee)),
+            }
+        }
+        _ => Err(reject(LuarmorMessage::NothingToSee)),
+    }
+}
+
+fn project<'a>(state: &'a mut FakeState, id: &str) -> Result<&'a mut FakeProject, Reply> {
+    state
+        .project_mut(id)
+        .ok_or_else(|| reject(LuarmorMessage::ProjectNotFound))
+}
+
+fn user<'a>(project: &'a mut FakeProject, user_key: &str) -> Result<&'a mut FakeUser, Reply> {
+    project
+        .users
+        .iter_mut()
+        .find(|x| x.user_key == user_key)
+        .ok_or_else(|| reject(LuarmorMessage::UserKeyNotFound))
+}
+
+/// Checks `discord_id` is valid and not linked to any key besides `user_key`.
+fn check_discord_id(project: &FakeProject, user_key: &str, discord_id: &str) -> Result<(), Reply> {
+    if !is_valid_discord_id(discord_id) {
+        Err(reject(LuarmorMessage::InvalidDiscordId))
+    } else if project
+        .users
+        .iter()
+        .any(|x| x.discord_id == discord_id && x.user_key != user_key)
+    {
+        Err(reject(LuarmorMessage::DiscordAlreadyExists))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_identifier(project: &FakeProject, user_key: &str, identifier: &str) -> Result<(), Reply> {
+    if project
+        .users
+        .iter()
+        .any(|x| x.identifier == identifier && x.user_key != user_key)
+    {
+        Err(reject(LuarmorMessage::IdentifierAlreadyExists))
+    } else {
+        Ok(())
+    }
+}
+
+fn status() -> Reply {
+    (
+        StatusCode::OK,
+        json!({
+            "version": "v3",
+            "active": true,
+            "message": LuarmorMessage::APIWorking.as_str(),
+            "warning": false,
+            "warning_message": "",
+        }),
+    )
+}
+
+fn details(state: &FakeState) -> Reply {
+    let projects: Vec<Value> = state
+        .projects
+        .iter()
+        .map(|project| {
+            json!({
+                "platform": "roblox",
+                "id": project.id,
+                "name": project.name,
+                "settings": { "reset_hwid_cooldown": project.reset_hwid_cooldown },
+                "scripts": project.scripts.iter().map(|script| json!({
+                    "script_name": script.script_name,
+                    "script_id": script.script_id,
+                    "script_version": script.script_version,
+                    "ffa": script.ffa,
+                    "silent": script.silent,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let account = &state.account;
+    reply(
+        LuarmorMessage::Success,
+        json!({
+            "email": account.email,
+            "discord_id": account.discord_id,
+            "expires_at": account.expires_at,
+            "registered_at": account.registered_at,
+            "enabled": 1,
+            "plan": account.plan,
+            "projects": projects,
+        }),
+    )
+}
+
+fn stats(state: &FakeState, no_users: bool) -> Reply {
+    let scripts: usize = state.projects.iter().map(|x| x.scripts.len()).sum();
+    let users: usize = state.projects.iter().map(|x| x.users.len()).sum();
+
+    let mut stats = json!({
+        "obfuscations": 0,
+        "scripts": scripts,
+        "attacks_blocked": 0,
+        "default": { "scripts": 100, "users": 100000, "obfuscations": 1000 },
+        "reset_at": state.now() + 30 * 86400,
+    });
+    if !no_users {
+        stats["users"] = users.into();
+    }
+
+    reply(
+        LuarmorMessage::Success,
+        json!({
+            "execution_data": { "frequency": 86400, "executions": [] },
+            "stats": stats,
+        }),
+    )
+}
+
+fn users(project: &FakeProject, query: &HashMap<String, String>) -> Reply {
+    let matches = |field: &str, value: &str| query.get(field).is_none_or(|x| x == value);
+    let search = query.get("search");
+
+    let users: Vec<&FakeUser> = project
+        .users
+        .iter()
+        .filter(|x| {
+            matches("user_key", &x.user_key)
+                && matches("discord_id", &x.discord_id)
+                && matches("identifier", &x.identifier)
+                && search.is_none_or(|search| {
+                    [&x.user_key, &x.identifier, &x.discord_id, &x.note]
+                        .iter()
+                        .any(|x| x.contains(search.as_str()))
+                })
+        })
+        .collect();
+
+    // `from` is inclusive, `until` is exclusive
+    let from = query
+        .get("from")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(0usize)
+        .min(users.len());
+    let until = query
+        .get("until")
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(users.len())
+        .clamp(from, users.len());
+
+    reply(
+        LuarmorMessage::Success,
+        json!({ "users": users[from..until] }),
+    )
+}
+
+#[derive(Deserialize)]
+struct CreateBody {
+    identifier: Option<String>,
+    auth_expire: Option<i64>,
+    note: Option<String>,
+    discord_id: Option<String>,
+    key_days: Option<u32>,
+}
+
+fn create_user(project: &mut FakeProject, now: i64, body: CreateBody) -> Result<Reply, Reply> {
+    let user_key = random_token(32);
+    let mut user = FakeUser::new(user_key.as_str());
+
+    if let Some(identifier) = body.identifier {
+        check_identifier(project, &user_key, &identifier)?;
+        user = user.with_identifier(identifier);
+    }
+    if let Some(discord_id) = body.discord_id {
+        check_discord_id(project, &user_key, &discord_id)?;
+        user = user.with_discord_id(discord_id);
+    }
+    user.note = body.note.unwrap_or_default();
+
+    // Keys which aren't claimed only start counting down once they are, see `CreateUser`
+    let claimed = !user.identifier.is_empty() || !user.discord_id.is_empty();
+    match (body.auth_expire, body.key_days) {
+        (Some(auth_expire), _) if claimed => user.auth_expire = auth_expire,
+        (Some(auth_expire), _) => {
+            user.key_days = Some(((auth_expire - now).max(0) as u64).div_ceil(86400) as u32)
+        }
+        (None, Some(key_days)) if claimed => user.auth_expire = now + i64::from(key_days) * 86400,
+        (None, key_days) => user.key_days = key_days,
+    }
+
+    project.users.push(user);
+    Ok(reply(
+        LuarmorMessage::Success,
+        json!({ "user_key": user_key }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct UpdateBody {
+    user_key: String,
+    identifier: Option<String>,
+    auth_expire: Option<i64>,
+    note: Option<String>,
+    discord_id: Option<String>,
+}
+
+fn update_user(project: &mut FakeProject, body: UpdateBody) -> Result<Reply, Reply> {
+    user(project, &body.user_key)?;
+    if let Some(identifier) = body.identifier.as_deref().filter(|x| !x.is_empty()) {
+        check_identifier(project, &body.user_key, identifier)?;
+    }
+    if let Some(discord_id) = body.discord_id.as_deref().filter(|x| !x.is_empty()) {
+        check_discord_id(project, &body.user_key, discord_id)?;
+    }
+
+    // An empty string clears the field
+    let user = user(project, &body.user_key)?;
+    if let Some(identifier) = body.identifier {
+        user.identifier_type = if identifier.is_empty() { "" } else { "HWID" }.to_string();
+        user.identifier = identifier;
+    }
+    if let Some(auth_expire) = body.auth_expire {
+        user.auth_expire = auth_expire;
+    }
+    if let Some(note) = body.note {
+        user.note = note;
+    }
+    if let Some(discord_id) = body.discord_id {
+        user.discord_id = discord_id;
+    }
+
+    Ok(reply(LuarmorMessage::EditSuccess, json!({})))
+}
+
+fn delete_user(project: &mut FakeProject, user_key: Option<&str>) -> Result<Reply, Reply> {
+    let index = project
+        .users
+        .iter()
+        .position(|x| Some(x.user_key.as_str()) == user_key)
+        .ok_or_else(|| reject(LuarmorMessage::UserKeyNotFound))?;
+
+    project.users.remove(index);
+    Ok(reply(LuarmorMessage::UserDeleted, json!({})))
+}
+
+#[derive(Deserialize)]
+struct ResetHwidBody {
+    user_key: String,
+    force: Option<bool>,
+}
+
+fn reset_hwid(project: &mut FakeProject, now: i64, body: ResetHwidBody) -> Result<Reply, Reply> {
+    let force = body.force.unwrap_or(false);
+    let (enabled, cooldown) = (project.reset_hwid_enabled, project.reset_hwid_cooldown);
+    let user = user(project, &body.user_key)?;
+
+    if user.banned != 0 {
+        return Err(reject(other("User is banned.")));
+    }
+    if !force && !enabled {
+        return Err(reject(LuarmorMessage::ResetHWIDDisabled));
+    }
+    if !force && cooldown > 0 && user.total_resets > 0 && now < user.last_reset + cooldown {
+        return Err(reject(LuarmorMessage::UserCooldown));
+    }
+
+    user.identifier.clear();
+    user.identifier_type.clear();
+    user.status = "reset".to_string();
+    user.last_reset = now;
+    user.total_resets += 1;
+    Ok(reply(LuarmorMessage::SuccessReset, json!({})))
+}
+
+#[derive(Deserialize)]
+struct LinkDiscordBody {
+    user_key: String,
+    discord_id: Option<String>,
+    force: Option<bool>,
+}
+
+fn link_discord(project: &mut FakeProject, body: LinkDiscordBody) -> Result<Reply, Reply> {
+    let discord_id = body.discord_id.unwrap_or_default();
+    let linked = user(project, &body.user_key)?.discord_id.clone();
+    check_discord_id(project, &body.user_key, &discord_id)?;
+    if !linked.is_empty() && linked != discord_id && !body.force.unwrap_or(false) {
+        return Err(reject(LuarmorMessage::DiscordAlreadyLinked));
+    }
+
+    user(project, &body.user_key)?.discord_id = discord_id;
+    Ok(reply(LuarmorMessage::DiscordIdSuccess, json!({})))
+}
+
+#[derive(Deserialize)]
+struct BlacklistBody {
+    user_key: String,
+    ban_reason: Option<String>,
+    ban_expire: Option<i64>,
+}
+
+fn blacklist(project: &mut FakeProject, body: BlacklistBody) -> Result<Reply, Reply> {
+    let user = user(project, &body.user_key)?;
+    user.banned = 1;
+    user.status = "banned".to_string();
+    user.ban_reason = body.ban_reason.unwrap_or_default();
+    user.ban_expire = body.ban_expire.unwrap_or(-1);
+    user.unban_token = random_token(32);
+    Ok(reply(LuarmorMessage::Success, json!({})))
+}
+
+fn unban(project: &mut FakeProject, unban_token: Option<&str>) -> Result<Reply, Reply> {
+    let user = project
+        .users
+        .iter_mut()
+        .find(|x| x.banned != 0 && Some(x.unban_token.as_str()) == unban_token)
+        .ok_or_else(|| reject(LuarmorMessage::KeyNotFound))?;
+
+    user.banned = 0;
+    user.status = if user.identifier.is_empty() {
+        "reset"
+    } else {
+        "active"
+    }
+    .to_string();
+    user.ban_reason.clear();
+    user.ban_expire = -1;
+    user.unban_token.clear();
+    Ok(reply(LuarmorMessage::Success, json!({})))
+}
+
+#[derive(Deserialize)]
+struct UpdateScriptBody {
+    script: String,
+    silent: Option<bool>,
+    ffa: Option<bool>,
+    heartbeat: Option<bool>,
+    lightning: Option<bool>,
+}
+
+fn update_script(
+    project: &mut FakeProject,
+    script_id: &str,
+    body: UpdateScriptBody,
+) -> Result<Reply, Reply> {
+    let script = project
+        .scripts
+        .iter_mut()
+        .find(|x| x.script_id == script_id)
+        .ok_or_else(|| reject(other("Script not found")))?;
+
+    script.source = body.script;
+    script.silent = body.silent.unwrap_or(script.silent);
+    script.ffa = body.ffa.unwrap_or(script.ffa);
+    script.heartbeat = body.heartbeat.unwrap_or(script.heartbeat);
+    script.lightning = body.lightning.unwrap_or(script.lightning);
+    script.script_version = (script.script_version.parse::<u32>().unwrap_or(0) + 1).to_string();
+    Ok(reply(LuarmorMessage::Success, json!({})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeScript;
+
+    const API_KEY: &str = "secret";
+    const NOW: i64 = 1700000000;
+
+    fn state() -> FakeState {
+        FakeState {
+            projects: vec![
+                FakeProject::new("project", "Project")
+                    .with_script(FakeScript::new("script", "Script"))
+                    .with_user(FakeUser::new("free"))
+                    .with_user(
+                        FakeUser::new("owned")
+                            .with_identifier("hwid")
+                            .with_discord_id("111"),
+                    ),
+            ],
+            now: Some(NOW),
+            ..Default::default()
+        }
+    }
+
+    fn request(method: Method, uri: &str, body: Option<Value>) -> Request<Vec<u8>> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(AUTHORIZATION, API_KEY)
+            .body(body.map_or_else(Vec::new, |x| x.to_string().into_bytes()))
+            .unwrap()
+    }
+
+    /// Sends `request`, checking the status and exact message of the reply.
+    fn expect(
+        state: &mut FakeState,
+        request: Request<Vec<u8>>,
+        status: StatusCode,
+        message: &str,
+    ) -> Value {
+        let response = handle(state, API_KEY, &request);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            (response.status(), body["message"].as_str()),
+            (status, Some(message))
+        );
+        body
+    }
+
+    fn send(
+        state: &mut FakeState,
+        method: Method,
+        uri: &str,
+        body: Value,
+        status: StatusCode,
+        message: &str,
+    ) -> Value {
+        let body = Some(body).filter(|x| !x.is_null());
+        expect(state, request(method, uri, body), status, message)
+    }
+
+    fn user<'a>(state: &'a FakeState, user_key: &str) -> &'a FakeUser {
+        let project = state.project("project").unwrap();
+        project
+            .users
+            .iter()
+            .find(|x| x.user_key == user_key)
+            .unwrap()
+    }
+
+    const USERS: &str = "/v3/projects/project/users";
+
+    #[test]
+    fn checks_the_api_key() {
+        let state = &mut state();
+        let uri = "/status";
+        expect(
+            state,
+            request(Method::GET, uri, None),
+            StatusCode::OK,
+            "API is up and working!",
+        );
+
+        let uri = "/v3/keys/wrong/details";
+        let message = "Wrong API key";
+        expect(
+            state,
+            request(Method::GET, uri, None),
+            StatusCode::FORBIDDEN,
+            message,
+        );
+
+        let mut unauthorized = request(Method::GET, USERS, None);
+        unauthorized.headers_mut().remove(AUTHORIZATION);
+        let message = "Invalid API key! Visit https://luarmor.net/ to get access.";
+        expect(state, unauthorized, StatusCode::FORBIDDEN, message);
+
+        let uri = "/v3/projects/missing/users";
+        let message = "Project not found!";
+        expect(
+            state,
+            request(Method::GET, uri, None),
+            StatusCode::NOT_FOUND,
+            message,
+        );
+
+        let uri = "/v3/projects/project/nowhere";
+        let message = "nothing to see here.";
+        expect(
+            state,
+            request(Method::GET, uri, None),
+            StatusCode::BAD_REQUEST,
+            message,
+        );
+    }
+
+    #[test]
+    fn filters_and_pages_users() {
+        let state = &mut state();
+        let keys = |body: Value| -> Vec<String> {
+            let users = body["users"].as_array().unwrap();
+            users
+                .iter()
+                .map(|x| x["user_key"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let body = send(
+            state,
+            Method::GET,
+            USERS,
+            Value::Null,
+            StatusCode::OK,
+            "Success!",
+        );
+        assert_eq!(keys(body), ["free", "owned"]);
+        let uri = format!("{USERS}?discord_id=111");
+        let body = send(
+            state,
+            Method::GET,
+            &uri,
+            Value::Null,
+            StatusCode::OK,
+            "Success!",
+        );
+        assert_eq!(keys(body), ["owned"]);
+        let uri = format!("{USERS}?search=ree");
+        let body = send(
+            state,
+            Method::GET,
+            &uri,
+            Value::Null,
+            StatusCode::OK,
+            "Success!",
+        );
+        assert_eq!(keys(body), ["free"]);
+        let uri = format!("{USERS}?from=1&until=5");
+        let body = send(
+            state,
+            Method::GET,
+            &uri,
+            Value::Null,
+            StatusCode::OK,
+            "Success!",
+        );
+        assert_eq!(keys(body), ["owned"]);
+    }
+
+    #[test]
+    fn create_rejects_conflicts() {
+        let state = &mut state();
+        let bad = StatusCode::BAD_REQUEST;
+        let body = json!({ "identifier": "hwid" });
+        send(
+            state,
+            Method::POST,
+            USERS,
+            body,
+            bad,
+            "Identifier already exists.",
+        );
+        let body = json!({ "discord_id": "abc" });
+        send(state, Method::POST, USERS, body, bad, "Invalid discord_id");
+        let body = json!({ "discord_id": "111" });
+        send(
+            state,
+            Method::POST,
+            USERS,
+            body,
+            bad,
+            "Discord ID already exists",
+        );
+        assert_eq!(state.project("project").unwrap().users.len(), 2);
+    }
+
+    #[test]
+    fn create_starts_the_clock_once_claimed() {
+        let state = &mut state();
+        let mut create = |body| {
+            let body = send(state, Method::POST, USERS, body, StatusCode::OK, "Success!");
+            body["user_key"].as_str().unwrap().to_string()
+        };
+        let unclaimed = create(json!({ "key_days": 3 }));
+        let claimed = create(json!({ "discord_id": "222", "key_days": 2 }));
+        let rounded = create(json!({ "auth_expire": NOW + 86400 + 1 }));
+
+        assert_eq!(unclaimed.len(), 32);
+        let unclaimed = user(state, &unclaimed);
+        assert_eq!((unclaimed.key_days, unclaimed.auth_expire), (Some(3), -1));
+        let claimed = user(state, &claimed);
+        assert_eq!(
+            (claimed.key_days, claimed.auth_expire),
+            (None, NOW + 2 * 86400)
+        );
+        assert_eq!(user(state, &rounded).key_days, Some(2));
+    }
+
+    #[test]
+    fn update_sets_and_clears_fields() {
+        let state = &mut state();
+        let edited = "User has been edited successfully!";
+        let body = json!({ "user_key": "owned", "identifier": "", "note": "hi" });
+        send(state, Method::PATCH, USERS, body, StatusCode::OK, edited);
+        let owned = user(state, "owned");
+        assert_eq!(
+            (owned.identifier.as_str(), owned.identifier_type.as_str()),
+            ("", "")
+        );
+        assert_eq!(owned.note, "hi");
+
+        let body = json!({ "user_key": "free", "identifier": "new" });
+        send(state, Method::PATCH, USERS, body, StatusCode::OK, edited);
+        assert_eq!(user(state, "free").identifier_type, "HWID");
+
+        let body = json!({ "user_key": "owned", "identifier": "new" });
+        let bad = StatusCode::BAD_REQUEST;
+        send(
+            state,
+            Method::PATCH,
+            USERS,
+            body,
+            bad,
+            "Identifier already exists.",
+        );
+        let body = json!({ "user_key": "missing", "note": "" });
+        let missing = "User key doesn't exist";
+        send(
+            state,
+            Method::PATCH,
+            USERS,
+            body,
+            StatusCode::NOT_FOUND,
+            missing,
+        );
+    }
+
+    #[test]
+    fn delete_removes_the_key() {
+        let state = &mut state();
+        let uri = format!("{USERS}?user_key=free");
+        let ok = StatusCode::OK;
+        send(
+            state,
+            Method::DELETE,
+            &uri,
+            Value::Null,
+            ok,
+            "User has been deleted!",
+        );
+        let missing = StatusCode::NOT_FOUND;
+        send(
+            state,
+            Method::DELETE,
+            &uri,
+            Value::Null,
+            missing,
+            "User key doesn't exist",
+        );
+    }
+
+    #[test]
+    fn reset_hwid_has_a_cooldown() {
+        let state = &mut state();
+        let uri = format!("{USERS}/resethwid");
+        let reset = |state: &mut FakeState, body, status, message| {
+            send(state, Method::POST, &uri, body, status, message);
+        };
+        let (ok, bad) = (StatusCode::OK, StatusCode::BAD_REQUEST);
+
+        reset(
+            state,
+            json!({ "user_key": "owned" }),
+            ok,
+            "Successfully reset!",
+        );
+        let owned = user(state, "owned");
+        assert_eq!(
+            (owned.status.as_str(), owned.identifier.as_str()),
+            ("reset", "")
+        );
+        assert_eq!((owned.last_reset, owned.total_resets), (NOW, 1));
+
+        reset(
+            state,
+            json!({ "user_key": "owned" }),
+            bad,
+            "User is on cooldown.",
+        );
+        let forced = json!({ "user_key": "owned", "force": true });
+        reset(state, forced.clone(), ok, "Successfully reset!");
+        state.now = Some(NOW + 86400);
+        reset(
+            state,
+            json!({ "user_key": "owned" }),
+            ok,
+            "Successfully reset!",
+        );
+
+        state.project_mut("project").unwrap().reset_hwid_enabled = false;
+        let disabled = "Reset Hwid is disabled for this script";
+        reset(state, json!({ "user_key": "free" }), bad, disabled);
+        reset(state, forced, ok, "Successfully reset!");
+
+        state.project_mut("project").unwrap().users[0].banned = 1;
+        let banned = json!({ "user_key": "free", "force": true });
+        reset(state, banned, bad, "User is banned.");
+    }
+
+    #[test]
+    fn link_discord_keeps_existing_links() {
+        let state = &mut state();
+        let uri = format!("{USERS}/linkdiscord");
+        let link = |state: &mut FakeState, body, status, message| {
+            send(state, Method::POST, &uri, body, status, message);
+        };
+        let (ok, bad) = (StatusCode::OK, StatusCode::BAD_REQUEST);
+        let linked = "Discord ID successfully linked!";
+
+        let body = json!({ "user_key": "owned", "discord_id": "222" });
+        let message = "This key already has a discord linked to it";
+        link(state, body, bad, message);
+        link(
+            state,
+            json!({ "user_key": "owned", "discord_id": "111" }),
+            ok,
+            linked,
+        );
+        let body = json!({ "user_key": "owned", "discord_id": "222", "force": true });
+        link(state, body, ok, linked);
+        assert_eq!(user(state, "owned").discord_id, "222");
+
+        let body = json!({ "user_key": "free", "discord_id": "222" });
+        link(state, body, bad, "Discord ID already exists");
+        let body = json!({ "user_key": "free", "discord_id": "not a number" });
+        link(state, body, bad, "Invalid discord_id");
+        link(
+            state,
+            json!({ "user_key": "free", "discord_id": "333" }),
+            ok,
+            linked,
+        );
+    }
+
+    #[test]
+    fn unban_tokens_lift_the_blacklist_once() {
+        let state = &mut state();
+        let uri = format!("{USERS}/blacklist");
+        let body = json!({ "user_key": "owned", "ban_reason": "leaking" });
+        send(state, Method::POST, &uri, body, StatusCode::OK, "Success!");
+        let owned = user(state, "owned").clone();
+        assert_eq!((owned.banned, owned.status.as_str()), (1, "banned"));
+        assert_eq!(
+            (owned.ban_reason.as_str(), owned.ban_expire),
+            ("leaking", -1)
+        );
+        assert_eq!(owned.unban_token.len(), 32);
+
+        // Unbanning needs no API key, only the token
+        let unban = |state: &mut FakeState, token: &str, status, message| {
+            let uri = format!("{USERS}/unban?unban_token={token}");
+            let mut request = request(Method::GET, &uri, None);
+            request.headers_mut().remove(AUTHORIZATION);
+            expect(state, request, status, message);
+        };
+        let missing = StatusCode::NOT_FOUND;
+        unban(state, &"a".repeat(32), missing, "Key not found");
+        unban(state, &owned.unban_token, StatusCode::OK, "Success!");
+        let unbanned = user(state, "owned");
+        assert_eq!((unbanned.banned, unbanned.status.as_str()), (0, "active"));
+        assert!(unbanned.unban_token.is_empty());
+        unban(state, &owned.unban_token, missing, "Key not found");
+    }
+
+    #[test]
+    fn update_script_bumps_the_version() {
+        let state = &mut state();
+        let body = json!({ "script": "print(1)", "ffa": true });
+        let uri = "/v3/projects/project/scripts/script";
+        send(
+            state,
+            Method::PUT,
+            uri,
+            body.clone(),
+            StatusCode::OK,
+            "Success!",
+        );
+        let script = &state.project("project").unwrap().scripts[0];
+        assert_eq!(
+            (script.script_version.as_str(), script.source.as_str()),
+            ("2", "print(1)")
+        );
+        assert!(script.ffa && script.heartbeat);
+
+        let uri = "/v3/projects/project/scripts/missing";
+        send(
+            state,
+            Method::PUT,
+            uri,
+            body,
+            StatusCode::BAD_REQUEST,
+            "Script not found",
+        );
+    }
+}
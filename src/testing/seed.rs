@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        ProjectId,
+        v3::{
+            keys::{ApiKeyDetailsResponse, Project, ResetHwidCooldown, Script},
+            projects::users::{User, UserExpiration},
+        },
+    },
+    testing::{FakeAccount, FakeProject, FakeScript, FakeState, FakeUser},
+};
+
+/// A [FakeState] described with this crate's models, e.g. to seed `luarmor-mock` from a file.
+///
+/// Values are read exactly like Luarmor's responses, so real responses can be captured as is.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct FakeSeed {
+    /// The account and its projects, see [crate::LuarmorClient::details].
+    pub details: ApiKeyDetailsResponse,
+    /// The users of each project in `details`, see [crate::LuarmorClient::users].
+    #[serde(default)]
+    pub users: BTreeMap<ProjectId, Vec<User>>,
+    /// If set, the clock is frozen at this unix timestamp.
+    #[serde(default)]
+    pub now: Option<i64>,
+}
+
+/// Users were seeded for a project which isn't in [FakeSeed::details].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, thiserror::Error)]
+#[error("users were given for unknown project {0}")]
+pub struct UnknownSeedProject(pub ProjectId);
+
+impl TryFrom<FakeSeed> for FakeState {
+    type Error = UnknownSeedProject;
+
+    fn try_from(mut value: FakeSeed) -> Result<Self, Self::Error> {
+        let details = value.details;
+        let projects = details
+            .projects
+            .into_iter()
+            .map(|project| {
+                let users = value.users.remove(&project.id).unwrap_or_default();
+                FakeProject {
+                    users: users.into_iter().map(Into::into).collect(),
+                    ..project.into()
+                }
+            })
+            .collect();
+        if let Some(project_id) = value.users.into_keys().next() {
+            return Err(UnknownSeedProject(project_id));
+        }
+
+        Ok(Self {
+            account: FakeAccount {
+                email: details.email,
+                discord_id: details
+                    .discord_id
+                    .map(|x| x.into_inner())
+                    .unwrap_or_default(),
+                expires_at: details.expires_at.unix_timestamp(),
+                registered_at: details.registered_at.unix_timestamp(),
+                plan: details.plan.as_str().to_string(),
+            },
+            projects,
+            now: value.now,
+        })
+    }
+}
+impl From<Project> for FakeProject {
+    fn from(value: Project) -> Self {
+        Self {
+            reset_hwid_cooldown: match value.settings.reset_hwid_cooldown {
+                ResetHwidCooldown::Never => -1,
+                ResetHwidCooldown::Specified(x) => x.unix_timestamp(),
+            },
+            scripts: value.scripts.into_iter().map(Into::into).collect(),
+            ..FakeProject::new(value.id.into_inner(), value.name)
+        }
+    }
+}
+impl From<Script> for FakeScript {
+    fn from(value: Script) -> Self {
+        Self {
+            script_version: value.script_version,
+            ffa: value.ffa,
+            silent: value.silent,
+            ..FakeScript::new(value.script_id.into_inner(), value.script_name)
+        }
+    }
+}
+impl From<User> for FakeUser {
+    fn from(value: User) -> Self {
+        Self {
+            user_key: value.user_key.map(|x| x.into_inner()).unwrap_or_default(),
+            identifier: value.identifier.map(|x| x.into_inner()).unwrap_or_default(),
+            identifier_type: value.identifier_type.as_str().to_string(),
+            discord_id: value.discord_id.map(|x| x.into_inner()).unwrap_or_default(),
+            status: value.status.as_str().to_string(),
+            last_reset: value.last_reset.unix_timestamp(),
+            total_resets: value.total_resets,
+            auth_expire: match value.auth_expire {
+                UserExpiration::Never => -1,
+                UserExpiration::Specified(x) => x.unix_timestamp(),
+            },
+            banned: value.banned.into(),
+            ban_reason: value.ban_reason.unwrap_or_default(),
+            ban_expire: value.ban_expire.unix_timestamp(),
+            unban_token: value
+                .unban_token
+                .map(|x| x.into_inner())
+                .unwrap_or_default(),
+            total_executions: value.total_executions,
+            note: value.note.unwrap_or_default(),
+            ban_ip: value.ban_ip.unwrap_or_default(),
+            key_days: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::v3::projects::users::test_user;
+
+    fn seed(users: serde_json::Value) -> serde_json::Result<FakeSeed> {
+        serde_json::from_value(json!({
+            "details": {
+                "email": "dev@example.com",
+                "discord_id": "",
+                "expires_at": 1900000000,
+                "registered_at": 1600000000,
+                "enabled": 1,
+                "plan": "r",
+                "projects": [{
+                    "platform": "roblox",
+                    "id": "abc123",
+                    "name": "Hub",
+                    "settings": { "reset_hwid_cooldown": -1 },
+                    "scripts": [{
+                        "script_name": "Main",
+                        "script_id": "s1",
+                        "script_version": "7",
+                        "ffa": false,
+                        "silent": true,
+                    }],
+                }],
+            },
+            "users": users,
+            "now": 1700000000,
+        }))
+    }
+
+    fn user() -> serde_json::Value {
+        let user = test_user(json!({
+            "identifier": "hwid",
+            "identifier_type": "HWID",
+            "total_resets": 2,
+            "auth_expire": 1800000000,
+            "banned": 1,
+            "ban_reason": "leaking",
+            "unban_token": "a".repeat(32),
+            "total_executions": 5,
+        }));
+        serde_json::to_value(user).unwrap()
+    }
+
+    #[test]
+    fn converts_into_state() {
+        let seed = seed(json!({
+            "abc123": [user()],
+        }))
+        .unwrap();
+        let state = FakeState::try_from(seed).unwrap();
+
+        assert_eq!(state.account.plan, "r");
+        assert_eq!(state.now, Some(1700000000));
+        let project = state.project("abc123").unwrap();
+        assert_eq!(project.reset_hwid_cooldown, -1);
+        assert_eq!(project.scripts[0].script_version, "7");
+        assert!(project.scripts[0].heartbeat);
+        assert_eq!(
+            project.users,
+            [FakeUser {
+                status: "active".to_string(),
+                total_resets: 2,
+                auth_expire: 1800000000,
+                banned: 1,
+                ban_reason: "leaking".to_string(),
+                unban_token: "a".repeat(32),
+                total_executions: 5,
+                ..FakeUser::new("key1").with_identifier("hwid")
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let mut user = user();
+        assert!(seed(json!({ "abc123": [user.clone()] })).is_ok());
+
+        user["auth_expire"] = "tomorrow".into();
+        assert!(seed(json!({ "abc123": [user] })).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_projects() {
+        let seed = seed(json!({ "other": [] })).unwrap();
+        assert_eq!(
+            FakeState::try_from(seed),
+            Err(UnknownSeedProject("other".parse().unwrap()))
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Everything the fake backend keeps in memory.
+///
+/// This (de)serializes to JSON, so it can be seeded from a file.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default, Serialize, Deserialize)]
+pub struct FakeState {
+    /// The account the API key belongs to.
+    #[serde(default)]
+    pub account: FakeAccount,
+    #[serde(default)]
+    pub projects: Vec<FakeProject>,
+    /// If set, the clock is frozen at this unix timestamp.
+    #[serde(default)]
+    pub now: Option<i64>,
+}
+impl FakeState {
+    /// The current unix timestamp, as seen by the fake.
+    pub fn now(&self) -> i64 {
+        self.now
+            .unwrap_or_else(|| OffsetDateTime::now_utc().unix_timestamp())
+    }
+
+    /// Finds a project by its ID.
+    pub fn project(&self, id: &str) -> Option<&FakeProject> {
+        self.projects.iter().find(|x| x.id == id)
+    }
+
+    /// Finds a project by its ID.
+    pub fn project_mut(&mut self, id: &str) -> Option<&mut FakeProject> {
+        self.projects.iter_mut().find(|x| x.id == id)
+    }
+}
+
+/// The account returned by [crate::models::v3::keys::ApiKeyDetails].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FakeAccount {
+    pub email: String,
+    pub discord_id: String,
+    pub expires_at: i64,
+    pub registered_at: i64,
+    /// The raw plan, e.g. `p` for premium.
+    pub plan: String,
+}
+impl Default for FakeAccount {
+    fn default() -> Self {
+        Self {
+            email: "fake@luarmor.net".to_string(),
+            discord_id: String::new(),
+            expires_at: i32::MAX.into(),
+            registered_at: 0,
+            plan: "p".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct FakeProject {
+    pub id: String,
+    pub name: String,
+    /// The number of seconds a user must wait between HWID resets, a negative value disables the cooldown.
+    #[serde(default = "default_reset_hwid_cooldown")]
+    pub reset_hwid_cooldown: i64,
+    /// If `false`, HWIDs can only be reset with `force`.
+    #[serde(default = "default_true")]
+    pub reset_hwid_enabled: bool,
+    #[serde(default)]
+    pub scripts: Vec<FakeScript>,
+    #[serde(default)]
+    pub users: Vec<FakeUser>,
+}
+impl FakeProject {
+    /// Creates an empty project.
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            reset_hwid_cooldown: default_reset_hwid_cooldown(),
+            reset_hwid_enabled: true,
+            scripts: Vec::new(),
+            users: Vec::new(),
+        }
+    }
+
+    /// Adds a script to the project.
+    pub fn with_script(mut self, script: FakeScript) -> Self {
+        self.scripts.push(script);
+        self
+    }
+
+    /// Adds a user to the project.
+    pub fn with_user(mut self, user: FakeUser) -> Self {
+        self.users.push(user);
+        self
+    }
+}
+
+fn default_reset_hwid_cooldown() -> i64 {
+    86400
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FakeScript {
+    pub script_id: String,
+    pub script_name: String,
+    pub script_version: String,
+    pub ffa: bool,
+    pub silent: bool,
+    pub heartbeat: bool,
+    pub lightning: bool,
+    /// The last uploaded source code.
+    pub source: String,
+}
+impl FakeScript {
+    /// Creates an empty script.
+    pub fn new(script_id: impl Into<String>, script_name: impl Into<String>) -> Self {
+        Self {
+            script_id: script_id.into(),
+            script_name: script_name.into(),
+            script_version: "1".to_string(),
+            heartbeat: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// A user, stored exactly as Luarmor sends it.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FakeUser {
+    pub user_key: String,
+    pub identifier: String,
+    pub identifier_type: String,
+    pub discord_id: String,
+    pub status: String,
+    pub last_reset: i64,
+    pub total_resets: u32,
+    pub auth_expire: i64,
+    pub banned: u8,
+    pub ban_reason: String,
+    pub ban_expire: i64,
+    pub unban_token: String,
+    pub total_executions: u64,
+    pub note: String,
+    pub ban_ip: String,
+    /// The days the key will have once it has been activated, if it hasn't been yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_days: Option<u32>,
+}
+impl Default for FakeUser {
+    fn default() -> Self {
+        Self {
+            user_key: String::new(),
+            identifier: String::new(),
+            identifier_type: String::new(),
+            discord_id: String::new(),
+            status: "reset".to_string(),
+            last_reset: 0,
+            total_resets: 0,
+            auth_expire: -1,
+            banned: 0,
+            ban_reason: String::new(),
+            ban_expire: -1,
+            unban_token: String::new(),
+            total_executions: 0,
+            note: String::new(),
+            ban_ip: String::new(),
+            key_days: None,
+        }
+    }
+}
+impl FakeUser {
+    /// Creates an unassigned, never expiring user.
+    pub fn new(user_key: impl Into<String>) -> Self {
+        Self {
+            user_key: user_key.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Links an identifier (HWID), activating the key.
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = identifier.into();
+        self.identifier_type = "HWID".to_string();
+        self.status = "active".to_string();
+        self
+    }
+
+    /// Links a Discord ID.
+    pub fn with_discord_id(mut self, discord_id: impl Into<String>) -> Self {
+        self.discord_id = discord_id.into();
+        self
+    }
+}
@@ -0,0 +1,21 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A random number, from the per-process random keys of [RandomState].
+///
+/// Good enough for jitter and fake IDs, not for anything security sensitive.
+pub(crate) fn random_u64() -> u64 {
+    // Each instance is keyed differently, so an empty hash is enough
+    RandomState::new().build_hasher().finish()
+}
+
+/// A random alphanumeric string, like the user keys and unban tokens Luarmor generates.
+pub(crate) fn random_token(len: usize) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    (0..len)
+        .map(|_| ALPHABET[(random_u64() % ALPHABET.len() as u64) as usize] as char)
+        .collect()
+}